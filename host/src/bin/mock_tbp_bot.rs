@@ -0,0 +1,111 @@
+//! 用于测试 TBP 适配器的机器人：维护 TBP 状态，用内置机器人的搜索给出位置
+
+use std::io::{self, BufRead, Write};
+
+use tetris::{new_default_piece_data, Bot, BotConfig, ExtendedBoard, Piece, Placement};
+use tetris_host::tbp::{board_from_tbp, location_to_piece, piece_to_location, piece_type, BotMessage, FrontendMessage, Move, Spin};
+
+const WIDTH: usize = 10;
+const HEIGHT: usize = 22;
+
+/// 机器人看到的游戏状态
+struct State {
+    board: ExtendedBoard,
+    hold: Option<usize>,
+    queue: Vec<usize>,
+}
+
+impl State {
+    fn suggest(&self, bot: &Bot) -> Vec<Move> {
+        let decision = match bot.think(&self.board, self.hold, true, &self.queue) {
+            Some(decision) => decision,
+            None => return Vec::new(),
+        };
+        let typ = if decision.hold {
+            self.hold.or(self.queue.get(1).copied())
+        } else {
+            self.queue.first().copied()
+        };
+        let Placement { position, orientation, spin } = decision.placement;
+        let location = typ.and_then(|typ| piece_to_location(&self.board.piece_data, &Piece { typ, position, orientation }, HEIGHT));
+        location.into_iter()
+            .map(|location| Move {
+                location,
+                spin: if spin { Spin::Full } else { Spin::None },
+            })
+            .collect()
+    }
+
+    fn play(&mut self, mv: &Move) {
+        let piece = match location_to_piece(&self.board.piece_data, &mv.location, HEIGHT) {
+            Some(piece) => piece,
+            None => return,
+        };
+        if self.queue.first() != Some(&piece.typ) && !self.queue.is_empty() {
+            let current = self.queue.remove(0);
+            if self.hold.is_none() && !self.queue.is_empty() {
+                self.queue.remove(0);
+            }
+            self.hold = Some(current);
+        } else if !self.queue.is_empty() {
+            self.queue.remove(0);
+        }
+        let piece_data = &self.board.piece_data[piece.typ];
+        self.board.board.lock_piece(piece_data, piece.position, piece.orientation, piece_data.color_id);
+        self.board.board.clear_filled_rows();
+    }
+}
+
+fn send(message: &BotMessage) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    serde_json::to_writer(&mut stdout, message)?;
+    stdout.write_all(b"\n")?;
+    stdout.flush()
+}
+
+fn main() -> io::Result<()> {
+    let bot = Bot::new(BotConfig {
+        depth: 2,
+        ..BotConfig::default()
+    });
+    let mut state: Option<State> = None;
+    send(&BotMessage::Info {
+        name: "mock".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        author: "tetris-host".to_string(),
+        features: Vec::new(),
+    })?;
+    for line in io::stdin().lock().lines() {
+        let message: FrontendMessage = match serde_json::from_str(&line?) {
+            Ok(message) => message,
+            Err(_) => continue,
+        };
+        match message {
+            FrontendMessage::Rules {} => send(&BotMessage::Ready)?,
+            FrontendMessage::Start(start) => {
+                let mut board = ExtendedBoard::new(WIDTH, HEIGHT, new_default_piece_data(), 0);
+                board.board = board_from_tbp(&start.board, WIDTH, HEIGHT);
+                let hold = start.hold.and_then(|name| piece_type(&board.piece_data, &name));
+                let queue = start.queue.iter().filter_map(|name| piece_type(&board.piece_data, name)).collect();
+                state = Some(State { board, hold, queue });
+            }
+            FrontendMessage::Stop => state = None,
+            FrontendMessage::Suggest => {
+                let moves = state.as_ref().map(|state| state.suggest(&bot)).unwrap_or_default();
+                send(&BotMessage::Suggestion { moves })?;
+            }
+            FrontendMessage::Play { mv } => {
+                if let Some(state) = &mut state {
+                    state.play(&mv);
+                }
+            }
+            FrontendMessage::NewPiece { piece } => {
+                if let Some(state) = &mut state {
+                    state.queue.extend(piece_type(&state.board.piece_data, &piece));
+                }
+            }
+            FrontendMessage::Quit => break,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,81 @@
+//! 离屏渲染录像：回放录像文件，把指定 tick 的画面保存为 PPM 或者 BMP 图片
+//!
+//! 用法：`render_replay [--tick N] [--size WxH] <录像文件> <图片文件>`
+//!
+//! 不指定 tick 时保存录像结束时的画面。图片扩展名为 `.bmp` 时保存为 BMP，否则保存为 PPM。
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+
+use tetris::{render_game, Replay};
+use tetris_host::image::Image;
+
+const USAGE: &str = "usage: render_replay [--tick N] [--size WxH] <replay> <image>";
+
+fn parse_size(value: &str) -> Option<(usize, usize)> {
+    let (width, height) = value.split_once('x')?;
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
+
+fn main() -> ExitCode {
+    let mut tick = None;
+    let mut size = (640, 480);
+    let mut paths = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--tick" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) => tick = Some(value),
+                None => {
+                    eprintln!("invalid value for --tick");
+                    return ExitCode::from(2);
+                }
+            },
+            "--size" => match args.next().as_deref().and_then(parse_size) {
+                Some(value) => size = value,
+                None => {
+                    eprintln!("invalid value for --size");
+                    return ExitCode::from(2);
+                }
+            },
+            _ => paths.push(arg),
+        }
+    }
+    let [replay_path, image_path] = paths.as_slice() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    // 版面居中，左边留出 Hold 的位置
+    if size.0 * 26 < size.1 * 20 {
+        eprintln!("image is too narrow, width must be at least 0.8 times the height");
+        return ExitCode::from(2);
+    }
+
+    let replay = match fs::read(replay_path).ok().and_then(|data| Replay::decode(&data)) {
+        Some(replay) => replay,
+        None => {
+            eprintln!("failed to read replay {}", replay_path);
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut game = match replay.new_game(false) {
+        Some(game) => game,
+        None => {
+            eprintln!("unknown piece set {}", replay.piece_set);
+            return ExitCode::FAILURE;
+        }
+    };
+    game.count_finesse = true;
+    let mut player = replay.player();
+    while tick.is_none_or(|tick| game.current_tick < tick) && player.step(&mut game) {}
+
+    let mut image = Image::new(size.0, size.1);
+    render_game(&mut game, &mut image, None, Some(replay.seed));
+    if let Err(err) = image.save(Path::new(image_path)) {
+        eprintln!("failed to write {}: {}", image_path, err);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
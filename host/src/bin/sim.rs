@@ -0,0 +1,100 @@
+//! 批量模拟：用内置机器人跑多局固定种子的游戏，每局输出一行 CSV
+//!
+//! 用法：`sim [--games N] [--seed N] [--pieces N] [--depth N] [--beam N] [--threads N] [--out FILE]`
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use tetris_host::sim::{simulate, SimOptions, CSV_HEADER};
+
+fn main() -> ExitCode {
+    let mut options = SimOptions::default();
+    let mut games = 100;
+    let mut first_seed: u32 = 0;
+    let mut threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let mut out = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next();
+        if arg == "--out" {
+            out = value;
+            continue;
+        }
+        if arg == "--seed" {
+            match value.and_then(|value| value.parse().ok()) {
+                Some(value) => first_seed = value,
+                None => {
+                    eprintln!("invalid value for {}", arg);
+                    return ExitCode::from(2);
+                }
+            }
+            continue;
+        }
+        let target = match arg.as_str() {
+            "--games" => &mut games,
+            "--pieces" => &mut options.max_pieces,
+            "--depth" => &mut options.bot.depth,
+            "--beam" => &mut options.bot.beam_width,
+            "--threads" => &mut threads,
+            _ => {
+                eprintln!("usage: sim [--games N] [--seed N] [--pieces N] [--depth N] [--beam N] [--threads N] [--out FILE]");
+                return ExitCode::from(2);
+            }
+        };
+        match value.and_then(|value| value.parse().ok()) {
+            Some(value) => *target = value,
+            None => {
+                eprintln!("invalid value for {}", arg);
+                return ExitCode::from(2);
+            }
+        }
+    }
+
+    // 所有种子都要能放进 u32
+    if games > 0 && u32::try_from(games - 1).ok().and_then(|last| first_seed.checked_add(last)).is_none() {
+        eprintln!("seeds from {} for {} games do not fit in u32", first_seed, games);
+        return ExitCode::from(2);
+    }
+
+    // 每个线程依次领取下一个种子
+    let next = AtomicUsize::new(0);
+    let mut results = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.max(1))
+            .map(|_| scope.spawn(|| {
+                let mut results = Vec::new();
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    if index >= games {
+                        break results;
+                    }
+                    // 上面已经检查过，不会溢出
+                    let seed = first_seed + index as u32;
+                    results.push(simulate(seed, &options));
+                }
+            }))
+            .collect();
+        workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect::<Vec<_>>()
+    });
+    results.sort_by_key(|result| result.seed);
+
+    let write = |writer: &mut dyn Write| -> io::Result<()> {
+        writeln!(writer, "{}", CSV_HEADER)?;
+        for result in &results {
+            writeln!(writer, "{}", result.to_csv())?;
+        }
+        writer.flush()
+    };
+    let written = match &out {
+        Some(path) => File::create(path).and_then(|file| write(&mut BufWriter::new(file))),
+        None => write(&mut io::stdout().lock()),
+    };
+    if let Err(err) = written {
+        eprintln!("failed to write results: {}", err);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,60 @@
+//! TBP 适配器：启动外部机器人，在我们的规则下进行一局单人游戏，输出结果
+//!
+//! 用法：`tbp [--seed N] [--pieces N] [--preview N] <机器人程序> [参数...]`
+
+use std::env;
+use std::process::ExitCode;
+
+use tetris_host::tbp::{run_match, BotProcess, EndReason, MatchOptions};
+
+fn main() -> ExitCode {
+    let mut options = MatchOptions::default();
+    let mut args = env::args().skip(1);
+    let program = loop {
+        let arg = match args.next() {
+            Some(arg) => arg,
+            None => {
+                eprintln!("usage: tbp [--seed N] [--pieces N] [--preview N] <bot> [args...]");
+                return ExitCode::from(2);
+            }
+        };
+        let value = match arg.as_str() {
+            "--seed" => args.next().and_then(|value| value.parse().ok()).map(|value| options.seed = value),
+            "--pieces" => args.next().and_then(|value| value.parse().ok()).map(|value| options.max_pieces = value),
+            "--preview" => args.next().and_then(|value| value.parse().ok()).map(|value| options.preview_count = value),
+            _ => break arg,
+        };
+        match value {
+            Some(()) => {}
+            None => {
+                eprintln!("invalid value for {}", arg);
+                return ExitCode::from(2);
+            }
+        }
+    };
+    let bot_args: Vec<String> = args.collect();
+
+    let mut bot = match BotProcess::spawn(&program, &bot_args) {
+        Ok(bot) => bot,
+        Err(err) => {
+            eprintln!("failed to start {}: {}", program, err);
+            return ExitCode::FAILURE;
+        }
+    };
+    match run_match(&mut bot, &options) {
+        Ok(result) => {
+            println!("bot {}", result.bot_name);
+            println!("pieces {}", result.pieces);
+            println!("lines {}", result.lines);
+            println!("end {:?}", result.end_reason);
+            match result.end_reason {
+                EndReason::PieceLimit | EndReason::TopOut => ExitCode::SUCCESS,
+                _ => ExitCode::FAILURE,
+            }
+        }
+        Err(err) => {
+            eprintln!("communication error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
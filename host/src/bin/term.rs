@@ -0,0 +1,87 @@
+//! 终端前端：在 Linux 终端里玩，不需要 UEFI 和 QEMU
+//!
+//! 按键与 UEFI 版相同，`Q` 或者 `Esc` 退出。
+//!
+//! 用法：`term [--seed N] [--training] [--practice] [--replay FILE]`
+
+use std::env;
+use std::io::{self, Read};
+use std::process::ExitCode;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tetris::{run_game, Config, Replay, DEFAULT_PIECE_SET};
+use tetris_host::term::{FileStorage, RawMode, StdClock, TermInput, TermRenderer};
+
+const USAGE: &str = "usage: term [--seed N] [--training] [--practice] [--replay FILE]";
+
+fn main() -> ExitCode {
+    let mut seed = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_nanos() as u32).unwrap_or(0);
+    // 按住要等系统的自动重复，比点按多出操作，所以 finesse 不按 DAS 计算
+    let mut config = Config {
+        finesse_das: false,
+        ..Config::default()
+    };
+    let mut storage = FileStorage::default();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) => seed = value,
+                None => {
+                    eprintln!("invalid value for --seed");
+                    return ExitCode::from(2);
+                }
+            },
+            "--training" => config.finesse_training = true,
+            "--practice" => config.practice = true,
+            "--replay" => storage.replay_path = args.next(),
+            _ => {
+                eprintln!("{}", USAGE);
+                return ExitCode::from(2);
+            }
+        }
+    }
+
+    let mut replay = Replay::new(seed, DEFAULT_PIECE_SET, 10, 22, 0, 5, config);
+    let mut game = replay.new_game(true).unwrap();
+
+    let raw_mode = match RawMode::enable() {
+        Ok(raw_mode) => raw_mode,
+        Err(err) => {
+            eprintln!("failed to enter raw mode: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // 读取标准输入会阻塞，放到单独的线程
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut stdin = io::stdin();
+        let mut buf = [0u8; 64];
+        while let Ok(len) = stdin.read(&mut buf) {
+            if len == 0 || sender.send(buf[..len].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut input = TermInput::new(receiver);
+    let end = run_game(&mut game, &mut replay, &mut input, &mut StdClock::default(), &mut TermRenderer, &mut storage);
+    drop(raw_mode);
+
+    println!(
+        "{:?}  seed {}  score {}  lines {}  pieces {}  finesse faults {}",
+        end,
+        seed,
+        game.score,
+        game.lines,
+        game.pieces,
+        game.finesse_faults,
+    );
+    if let Some(path) = &storage.replay_path {
+        println!("replay saved to {}", path);
+    }
+    ExitCode::SUCCESS
+}
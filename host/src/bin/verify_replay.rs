@@ -0,0 +1,94 @@
+//! 录像校验：回放录像文件，输出最终得分、行数、时间和校验值
+//!
+//! 回放结果与录像不一致（不同步），或者与声明的结果不一致时，以非零状态退出。
+//!
+//! 用法：`verify_replay [--tick-rate HZ] <录像文件>...`
+
+use std::env;
+use std::fs;
+use std::process::ExitCode;
+
+use tetris::{Replay, ReplayResult};
+
+/// UEFI 版每 15 毫秒一个 tick
+const DEFAULT_TICK_RATE: f64 = 1000.0 / 15.0;
+
+/// 校验一个录像文件
+///
+/// returns: bool 是否通过
+fn verify(path: &str, tick_rate: f64) -> bool {
+    println!("{}", path);
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(err) => {
+            println!("  error: {}", err);
+            return false;
+        }
+    };
+    let replay = match Replay::decode(&data) {
+        Some(replay) => replay,
+        None => {
+            println!("  error: not a replay, unsupported version or invalid parameters");
+            return false;
+        }
+    };
+    let game = match replay.play() {
+        Some(game) => game,
+        None => {
+            println!("  error: unknown piece set {}", replay.piece_set);
+            return false;
+        }
+    };
+    let result = ReplayResult::from_game(&game);
+    println!("  score    {}", result.score);
+    println!("  lines    {}", result.lines);
+    println!("  pieces   {}", result.pieces);
+    println!("  time     {} ticks ({:.2} s)", game.current_tick, game.current_tick as f64 / tick_rate);
+    println!("  checksum {:016x}", game.checksum());
+
+    let mut ok = true;
+    if !replay.matches(&game) {
+        println!("  DESYNC: recorded end tick {}, checksum {:016x}", replay.end_tick, replay.checksum);
+        ok = false;
+    }
+    match replay.result {
+        Some(claimed) if claimed != result => {
+            println!("  MISMATCH: claimed score {}, lines {}, pieces {}", claimed.score, claimed.lines, claimed.pieces);
+            ok = false;
+        }
+        Some(_) => {}
+        None => println!("  (no claimed result)"),
+    }
+    println!("  {}", if ok { "OK" } else { "FAILED" });
+    ok
+}
+
+fn main() -> ExitCode {
+    let mut tick_rate = DEFAULT_TICK_RATE;
+    let mut paths = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--tick-rate" {
+            match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) => tick_rate = value,
+                None => {
+                    eprintln!("invalid value for --tick-rate");
+                    return ExitCode::from(2);
+                }
+            }
+        } else {
+            paths.push(arg);
+        }
+    }
+    if paths.is_empty() {
+        eprintln!("usage: verify_replay [--tick-rate HZ] <replay>...");
+        return ExitCode::from(2);
+    }
+    // 全部校验完再退出，方便一次检查多个文件
+    let failed = paths.iter().filter(|path| !verify(path, tick_rate)).count();
+    if failed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
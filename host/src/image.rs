@@ -0,0 +1,100 @@
+//! ## 离屏渲染
+//!
+//! 实现 Framebuffer 的内存图片，可以保存为 PPM 或者 BMP 文件。
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use tetris::{Framebuffer, Rgb};
+
+/// 内存中的 RGB 图片
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    /// 从左上角开始逐行排列
+    pub pixels: Vec<Rgb>,
+}
+
+impl Image {
+    /// 创建黑色图片
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![Rgb::default(); width * height],
+        }
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Option<Rgb> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.pixels.get(y * self.width + x).copied()
+    }
+
+    /// 编码为二进制 PPM（P6）
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut data = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        for pixel in &self.pixels {
+            data.extend_from_slice(&[pixel.r, pixel.g, pixel.b]);
+        }
+        data
+    }
+
+    /// 编码为 24 位 BMP。行从下往上存放，每行补齐到 4 字节
+    pub fn to_bmp(&self) -> Vec<u8> {
+        let row_size = (self.width * 3).div_ceil(4) * 4;
+        let image_size = row_size * self.height;
+        let file_size = 54 + image_size;
+        let mut data = Vec::with_capacity(file_size);
+        data.extend_from_slice(b"BM");
+        data.extend_from_slice(&(file_size as u32).to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&54u32.to_le_bytes());
+        // BITMAPINFOHEADER
+        data.extend_from_slice(&40u32.to_le_bytes());
+        data.extend_from_slice(&(self.width as i32).to_le_bytes());
+        data.extend_from_slice(&(self.height as i32).to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&24u16.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&(image_size as u32).to_le_bytes());
+        data.extend_from_slice(&2835i32.to_le_bytes());
+        data.extend_from_slice(&2835i32.to_le_bytes());
+        data.extend_from_slice(&[0; 8]);
+        for row in self.pixels.chunks(self.width.max(1)).rev() {
+            let start = data.len();
+            for pixel in row {
+                data.extend_from_slice(&[pixel.b, pixel.g, pixel.r]);
+            }
+            data.resize(start + row_size, 0);
+        }
+        data
+    }
+
+    /// 保存图片，扩展名为 `.bmp` 时保存为 BMP，否则保存为 PPM
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let is_bmp = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("bmp"));
+        fs::write(path, if is_bmp { self.to_bmp() } else { self.to_ppm() })
+    }
+}
+
+impl Framebuffer for Image {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn fill_rect(&mut self, x: usize, y: usize, cx: usize, cy: usize, color: Rgb) {
+        for y1 in y..y.saturating_add(cy).min(self.height) {
+            for x1 in x..x.saturating_add(cx).min(self.width) {
+                self.pixels[y1 * self.width + x1] = color;
+            }
+        }
+    }
+}
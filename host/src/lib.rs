@@ -0,0 +1,6 @@
+#![allow(clippy::result_unit_err)]
+
+pub mod image;
+pub mod sim;
+pub mod tbp;
+pub mod term;
@@ -0,0 +1,108 @@
+//! ## 批量模拟
+//!
+//! 不渲染，用内置机器人跑大量固定种子的对局，用于调整评估权重和发现引擎的回归。
+
+use tetris::{new_default_piece_data, Bot, BotConfig, Config, ExtendedBoard, Game, PreviewGenerator, SevenBagGenerator};
+
+/// CSV 表头
+pub const CSV_HEADER: &str = "seed,pieces,lines,max_height,holes,end_reason";
+
+/// 模拟设置
+#[derive(Clone, Copy, Debug)]
+pub struct SimOptions {
+    /// 最多放置的方块数
+    pub max_pieces: usize,
+    /// 平均每个方块最多经过的 tick 数，超过则认为卡住
+    pub max_ticks_per_piece: usize,
+    pub preview_count: usize,
+    pub bot: BotConfig,
+}
+
+impl Default for SimOptions {
+    fn default() -> Self {
+        Self {
+            max_pieces: 1000,
+            max_ticks_per_piece: 1000,
+            preview_count: 5,
+            bot: BotConfig {
+                pps: 0.0,
+                depth: 2,
+                ..BotConfig::default()
+            },
+        }
+    }
+}
+
+/// 对局结束的原因
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimEndReason {
+    /// 无法放置新方块
+    TopOut,
+    /// 达到方块数上限
+    PieceLimit,
+    /// 达到 tick 数上限
+    TickLimit,
+}
+
+impl SimEndReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SimEndReason::TopOut => "top_out",
+            SimEndReason::PieceLimit => "piece_limit",
+            SimEndReason::TickLimit => "tick_limit",
+        }
+    }
+}
+
+/// 一局的统计
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SimResult {
+    pub seed: u32,
+    pub pieces: usize,
+    pub lines: usize,
+    /// 结束时的最大列高度
+    pub max_height: usize,
+    /// 结束时的空洞数
+    pub holes: usize,
+    pub end_reason: SimEndReason,
+}
+
+impl SimResult {
+    /// 转为一行 CSV，不含换行
+    pub fn to_csv(&self) -> String {
+        format!("{},{},{},{},{},{}", self.seed, self.pieces, self.lines, self.max_height, self.holes, self.end_reason.as_str())
+    }
+}
+
+/// 用指定种子跑一局
+pub fn simulate(seed: u32, options: &SimOptions) -> SimResult {
+    let board = ExtendedBoard::new(10, 22, new_default_piece_data(), 0);
+    let types = board.piece_data.len();
+    let mut game = Game::new(Config::default(), board, PreviewGenerator::new(SevenBagGenerator::new(seed, types), options.preview_count));
+    let mut bot = Bot::new(options.bot);
+    let max_ticks = options.max_pieces.saturating_mul(options.max_ticks_per_piece);
+    let end_reason = if game.add_next_piece().is_err() {
+        SimEndReason::TopOut
+    } else {
+        loop {
+            if game.pieces >= options.max_pieces {
+                break SimEndReason::PieceLimit;
+            }
+            if game.current_tick >= max_ticks {
+                break SimEndReason::TickLimit;
+            }
+            if bot.step(&mut game).is_err() {
+                break SimEndReason::TopOut;
+            }
+        }
+    };
+    let analysis = game.board.board.analyze();
+    SimResult {
+        seed,
+        pieces: game.pieces,
+        lines: game.lines,
+        max_height: analysis.max_height,
+        holes: analysis.holes,
+        end_reason,
+    }
+}
@@ -0,0 +1,431 @@
+//! ## Tetris Bot Protocol (TBP)
+//!
+//! 协议见 <https://github.com/tetris-bot-protocol/tbp-spec>。前端和机器人通过标准输入输出交换单行 JSON 消息。
+//!
+//! TBP 版面为 40 行，第 0 行为最下面一行；方块位置为 SRS 旋转中心，与 [`CenterPosition`] 相同。
+
+use std::cell::RefCell;
+use std::io::{self, BufRead, BufReader, Write};
+use std::iter;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+use tetris::{color_id_to_char, char_to_color_id, from_center_position, simulate_path, to_center_position, Board, CenterPosition, Config, Event, ExtendedBoard, Game, Input, Piece, PieceData, PreviewGenerator, SevenBagGenerator};
+
+/// TBP 版面的行数
+pub const TBP_BOARD_HEIGHT: usize = 40;
+
+/// 前端发给机器人的消息
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FrontendMessage {
+    Rules {},
+    Start(Start),
+    Stop,
+    Suggest,
+    Play {
+        #[serde(rename = "move")]
+        mv: Move,
+    },
+    NewPiece {
+        piece: String,
+    },
+    Quit,
+}
+
+/// 机器人发给前端的消息
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BotMessage {
+    Info {
+        name: String,
+        version: String,
+        author: String,
+        #[serde(default)]
+        features: Vec<String>,
+    },
+    Ready,
+    Error {
+        reason: String,
+    },
+    Suggestion {
+        moves: Vec<Move>,
+    },
+}
+
+/// 开始计算时的完整状态
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Start {
+    pub hold: Option<String>,
+    /// 当前方块和预览
+    pub queue: Vec<String>,
+    pub combo: u32,
+    pub back_to_back: bool,
+    /// 40 行，第 0 行为最下面一行
+    pub board: Vec<Vec<Option<String>>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Move {
+    pub location: Location,
+    pub spin: Spin,
+}
+
+/// 方块位置
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Location {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub orientation: Orientation,
+    pub x: isize,
+    pub y: isize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Orientation {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Orientation {
+    /// 转为朝向编号：0 初始，1 向右，2 180 度，3 向左
+    pub fn index(&self) -> usize {
+        match self {
+            Orientation::North => 0,
+            Orientation::East => 1,
+            Orientation::South => 2,
+            Orientation::West => 3,
+        }
+    }
+
+    pub fn from_index(orientation: usize) -> Self {
+        match orientation % 4 {
+            0 => Orientation::North,
+            1 => Orientation::East,
+            2 => Orientation::South,
+            _ => Orientation::West,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Spin {
+    None,
+    Mini,
+    Full,
+}
+
+/// 方块类型转为 TBP 方块名称
+pub fn piece_name(piece_data: &[PieceData], typ: usize) -> Option<String> {
+    piece_data.get(typ).map(|data| data.name.clone())
+}
+
+/// TBP 方块名称转为方块类型
+pub fn piece_type(piece_data: &[PieceData], name: &str) -> Option<usize> {
+    piece_data.iter().position(|data| data.name == name)
+}
+
+/// 版面转为 TBP 版面，最下面一行对齐
+pub fn board_to_tbp(board: &Board) -> Vec<Vec<Option<String>>> {
+    (0..TBP_BOARD_HEIGHT)
+        .map(|row| {
+            (0..board.width)
+                .map(|x| {
+                    let cell = board.height.checked_sub(row + 1).and_then(|y| board.get_cell(x, y));
+                    cell.and_then(|cell| cell.map(|color_id| color_id_to_char(color_id).to_string()))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// TBP 版面转为版面，最下面一行对齐，超出高度的行被丢弃
+pub fn board_from_tbp(cells: &[Vec<Option<String>>], width: usize, height: usize) -> Board {
+    let mut board = Board::new(width, height);
+    for (row, cells) in cells.iter().enumerate().take(height) {
+        for (x, cell) in cells.iter().enumerate() {
+            if let Some(cell) = cell {
+                let color_id = cell.chars().next().and_then(char_to_color_id);
+                board.set_cell(x, height - 1 - row, color_id);
+            }
+        }
+    }
+    board
+}
+
+/// 方块转为 TBP 位置
+pub fn piece_to_location(piece_data: &[PieceData], piece: &Piece, height: usize) -> Option<Location> {
+    let center = to_center_position(piece_data, piece, height)?;
+    Some(Location {
+        kind: center.name.to_string(),
+        orientation: Orientation::from_index(center.orientation),
+        x: center.x,
+        y: center.y,
+    })
+}
+
+/// TBP 位置转为方块
+pub fn location_to_piece(piece_data: &[PieceData], location: &Location, height: usize) -> Option<Piece> {
+    let center = CenterPosition {
+        name: location.kind.chars().next()?,
+        orientation: location.orientation.index(),
+        x: location.x,
+        y: location.y,
+    };
+    from_center_position(piece_data, &center, height)
+}
+
+/// 外部机器人进程
+pub struct BotProcess {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl BotProcess {
+    /// 启动机器人进程
+    pub fn spawn(program: &str, args: &[String]) -> io::Result<Self> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().ok_or(io::ErrorKind::BrokenPipe)?;
+        let stdout = BufReader::new(child.stdout.take().ok_or(io::ErrorKind::BrokenPipe)?);
+        Ok(Self {
+            child,
+            stdin,
+            stdout,
+        })
+    }
+
+    pub fn send(&mut self, message: &FrontendMessage) -> io::Result<()> {
+        serde_json::to_writer(&mut self.stdin, message)?;
+        self.stdin.write_all(b"\n")?;
+        self.stdin.flush()
+    }
+
+    /// 读取一条消息。进程退出时返回 None
+    pub fn recv(&mut self) -> io::Result<Option<BotMessage>> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.stdout.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            if !line.trim().is_empty() {
+                return Ok(Some(serde_json::from_str(&line)?));
+            }
+        }
+    }
+}
+
+impl Drop for BotProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// 记录产生过的方块，用于在预览中出现新方块时通知机器人
+///
+/// 克隆之后共享同一个记录
+#[derive(Clone)]
+pub struct Recorder<G> {
+    rng: G,
+    generated: Rc<RefCell<Vec<usize>>>,
+}
+
+impl<G> Recorder<G> {
+    pub fn new(rng: G) -> (Self, Rc<RefCell<Vec<usize>>>) {
+        let generated = Rc::new(RefCell::new(Vec::new()));
+        (Self {
+            rng,
+            generated: generated.clone(),
+        }, generated)
+    }
+}
+
+impl<G> Iterator for Recorder<G>
+    where
+        G: Iterator<Item=usize>
+{
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let typ = self.rng.next()?;
+        self.generated.borrow_mut().push(typ);
+        Some(typ)
+    }
+}
+
+/// 对局设置
+#[derive(Clone, Copy, Debug)]
+pub struct MatchOptions {
+    /// 7-Bag 的随机数种子
+    pub seed: u32,
+    /// 最多放置的方块数
+    pub max_pieces: usize,
+    /// 预览数
+    pub preview_count: usize,
+}
+
+impl Default for MatchOptions {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            max_pieces: 1000,
+            preview_count: 5,
+        }
+    }
+}
+
+/// 对局结束的原因
+#[derive(Clone, Debug, PartialEq)]
+pub enum EndReason {
+    /// 达到方块数上限
+    PieceLimit,
+    /// 无法放置新方块
+    TopOut,
+    /// 机器人给出的位置无法到达
+    InvalidMove(Move),
+    /// 机器人没有给出任何位置
+    NoMove,
+    /// 机器人拒绝了规则
+    Rejected(String),
+    /// 机器人退出或者发送了意外的消息
+    Disconnected,
+}
+
+/// 对局结果
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchResult {
+    pub bot_name: String,
+    pub pieces: usize,
+    pub lines: usize,
+    pub end_reason: EndReason,
+}
+
+type TbpGame = Game<PreviewGenerator<usize, Recorder<SevenBagGenerator>>>;
+
+/// 按照机器人给出的位置，通过事件放置当前方块，必要时先 Hold
+///
+/// returns: Result<bool, ()> 位置无法到达时返回 Ok(false)，游戏结束时返回 Err
+pub fn play_move(game: &mut TbpGame, mv: &Move) -> Result<bool, ()> {
+    let height = game.board.board.height;
+    let piece = match location_to_piece(&game.board.piece_data, &mv.location, height) {
+        Some(piece) => piece,
+        None => return Ok(false),
+    };
+    let current_typ = match &game.board.current_piece {
+        Some(current_piece) => current_piece.typ,
+        None => return Err(()),
+    };
+    if piece.typ != current_typ {
+        let hold_typ = game.hold.or_else(|| game.rng.preview().next().copied());
+        if game.hold_used || hold_typ != Some(piece.typ) {
+            return Ok(false);
+        }
+        game.on_event(Event::Hold)?;
+    }
+    // 模拟时不能从 game.rng 取方块：Recorder 的副本共享同一个记录
+    let mut simulation = game.with_rng(iter::empty());
+    let events = match simulate_path(&mut simulation, (piece.position, piece.orientation), &Input::moves(true, true)) {
+        Some(events) => events,
+        None => return Ok(false),
+    };
+    for event in events {
+        game.on_event(event)?;
+    }
+    Ok(true)
+}
+
+/// 与机器人进行一局单人游戏
+pub fn run_match(bot: &mut BotProcess, options: &MatchOptions) -> io::Result<MatchResult> {
+    let mut result = MatchResult {
+        bot_name: String::new(),
+        pieces: 0,
+        lines: 0,
+        end_reason: EndReason::Disconnected,
+    };
+    match bot.recv()? {
+        Some(BotMessage::Info { name, .. }) => result.bot_name = name,
+        _ => return Ok(result),
+    }
+    bot.send(&FrontendMessage::Rules {})?;
+    match bot.recv()? {
+        Some(BotMessage::Ready) => {}
+        Some(BotMessage::Error { reason }) => {
+            result.end_reason = EndReason::Rejected(reason);
+            return Ok(result);
+        }
+        _ => return Ok(result),
+    }
+
+    let board = ExtendedBoard::new(10, 22, tetris::new_default_piece_data(), 0);
+    let types = board.piece_data.len();
+    let (rng, generated) = Recorder::new(SevenBagGenerator::new(options.seed, types));
+    let config = Config {
+        gravity: 0.0,
+        ..Config::default()
+    };
+    let mut game = Game::new(config, board, PreviewGenerator::new(rng, options.preview_count));
+    if game.add_next_piece().is_err() {
+        result.end_reason = EndReason::TopOut;
+        return Ok(result);
+    }
+
+    let mut queue = Vec::new();
+    if let Some(current_piece) = &game.board.current_piece {
+        queue.extend(piece_name(&game.board.piece_data, current_piece.typ));
+    }
+    let preview: Vec<usize> = game.rng.preview().copied().collect();
+    queue.extend(preview.iter().filter_map(|typ| piece_name(&game.board.piece_data, *typ)));
+    bot.send(&FrontendMessage::Start(Start {
+        hold: game.hold.and_then(|typ| piece_name(&game.board.piece_data, typ)),
+        queue,
+        combo: 0,
+        back_to_back: false,
+        board: board_to_tbp(&game.board.board),
+    }))?;
+    let mut sent = generated.borrow().len();
+
+    result.end_reason = loop {
+        if game.pieces >= options.max_pieces {
+            break EndReason::PieceLimit;
+        }
+        bot.send(&FrontendMessage::Suggest)?;
+        let mv = match bot.recv()? {
+            Some(BotMessage::Suggestion { moves }) => match moves.into_iter().next() {
+                Some(mv) => mv,
+                None => break EndReason::NoMove,
+            },
+            _ => break EndReason::Disconnected,
+        };
+        match play_move(&mut game, &mv) {
+            Ok(true) => {}
+            Ok(false) => break EndReason::InvalidMove(mv),
+            Err(()) => break EndReason::TopOut,
+        }
+        bot.send(&FrontendMessage::Play { mv })?;
+        // 放置后预览中出现的新方块
+        game.rng.preview();
+        let new_pieces: Vec<usize> = generated.borrow()[sent..].to_vec();
+        sent += new_pieces.len();
+        for typ in new_pieces {
+            if let Some(piece) = piece_name(&game.board.piece_data, typ) {
+                bot.send(&FrontendMessage::NewPiece { piece })?;
+            }
+        }
+    };
+    result.pieces = game.pieces;
+    result.lines = game.lines;
+    // 机器人可能已经退出
+    let _ = bot.send(&FrontendMessage::Quit);
+    Ok(result)
+}
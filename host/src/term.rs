@@ -0,0 +1,407 @@
+//! ## 终端前端
+//!
+//! 在 Linux 终端里运行 Game：ANSI 真彩色渲染，原始模式读取键盘，画面布局与 UEFI 版的 render 相同。
+//!
+//! 终端只能收到按键按下和系统的自动重复，收不到抬起。第一次按下算作点按；
+//! 连续收到间隔很短的重复时认为按住，重复停止超过 RELEASE_MS 后认为抬起。
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::Receiver;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tetris::{Action, Clock, Event, Game, InputSource, Piece, PreviewGenerator, Renderer, Replay, ReplayGame, Rgb, Storage, COLOR_TABLE};
+/// 两次按下的间隔小于这个值时认为是自动重复；按住时超过这个值没有重复则认为抬起
+pub const RELEASE_MS: u64 = 100;
+
+/// 原始模式：不回显，逐字节读取。drop 时恢复原来的终端设置
+pub struct RawMode {
+    saved: String,
+}
+
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty").args(args).stdin(Stdio::inherit()).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other("stty failed, stdin is not a terminal?"));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+impl RawMode {
+    /// 进入原始模式，切换到备用屏幕并隐藏光标
+    pub fn enable() -> io::Result<Self> {
+        let saved = stty(&["-g"])?;
+        stty(&["raw", "-echo"])?;
+        let mut stdout = io::stdout();
+        stdout.write_all(b"\x1b[?1049h\x1b[?25l\x1b[2J")?;
+        stdout.flush()?;
+        Ok(Self { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(b"\x1b[0m\x1b[?25h\x1b[?1049l");
+        let _ = stdout.flush();
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+/// 按键
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    Escape,
+}
+
+/// 解析从终端读到的字节。方向键是 `ESC [ A` 这样的转义序列
+pub fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            0x1b => {
+                if bytes.get(i + 1) == Some(&b'[') {
+                    if let Some(c) = bytes.get(i + 2) {
+                        match c {
+                            b'A' => keys.push(Key::Up),
+                            b'B' => keys.push(Key::Down),
+                            b'C' => keys.push(Key::Right),
+                            b'D' => keys.push(Key::Left),
+                            _ => {}
+                        }
+                        i += 3;
+                        continue;
+                    }
+                }
+                keys.push(Key::Escape);
+            }
+            c if c.is_ascii() => keys.push(Key::Char(c.to_ascii_lowercase() as char)),
+            _ => {}
+        }
+        i += 1;
+    }
+    keys
+}
+
+/// 按下一个可以按住的键的结果
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Press {
+    /// 点按：按下后立即抬起
+    Tap,
+    /// 开始按住
+    Hold,
+    /// 按住时的自动重复，忽略
+    Repeat,
+}
+
+/// 通过自动重复的时间推断一个键是否按住
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HeldKey {
+    last_press_ms: Option<u64>,
+    held: bool,
+}
+
+impl HeldKey {
+    /// 收到一次按下
+    pub fn press(&mut self, now_ms: u64) -> Press {
+        let repeated = self.last_press_ms.is_some_and(|last| now_ms.saturating_sub(last) < RELEASE_MS);
+        self.last_press_ms = Some(now_ms);
+        if self.held {
+            Press::Repeat
+        } else if repeated {
+            self.held = true;
+            Press::Hold
+        } else {
+            Press::Tap
+        }
+    }
+
+    /// 检查按住的键是否已经抬起
+    ///
+    /// returns: bool 这一次检查时抬起
+    pub fn release(&mut self, now_ms: u64) -> bool {
+        if self.held && self.last_press_ms.is_some_and(|last| now_ms.saturating_sub(last) >= RELEASE_MS) {
+            self.held = false;
+            return true;
+        }
+        false
+    }
+
+    pub fn is_held(&self) -> bool {
+        self.held
+    }
+}
+
+/// 终端键盘输入。从 receiver 收到读取的字节，左、右、软降可以按住
+pub struct TermInput {
+    receiver: Receiver<Vec<u8>>,
+    left: HeldKey,
+    right: HeldKey,
+    down: HeldKey,
+    pending: VecDeque<Action>,
+}
+
+impl TermInput {
+    pub fn new(receiver: Receiver<Vec<u8>>) -> Self {
+        Self {
+            receiver,
+            left: HeldKey::default(),
+            right: HeldKey::default(),
+            down: HeldKey::default(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// 可以按住的键和它的开始、结束事件
+    fn held_key(&mut self, key: Key) -> Option<(&mut HeldKey, Event, Event)> {
+        match key {
+            Key::Left => Some((&mut self.left, Event::MoveLeftBegin, Event::MoveLeftEnd)),
+            Key::Right => Some((&mut self.right, Event::MoveRightBegin, Event::MoveRightEnd)),
+            Key::Down => Some((&mut self.down, Event::SoftDropBegin, Event::SoftDropEnd)),
+            _ => None,
+        }
+    }
+
+    fn press(&mut self, key: Key, now_ms: u64) {
+        if let Some((held_key, begin, end)) = self.held_key(key) {
+            let events: &[Event] = match held_key.press(now_ms) {
+                Press::Tap => &[begin, end],
+                Press::Hold => &[begin],
+                Press::Repeat => &[],
+            };
+            self.pending.extend(events.iter().map(|event| Action::Event(*event)));
+            return;
+        }
+        let action = match key {
+            Key::Char('q') | Key::Escape => Action::Quit,
+            Key::Char('r') => Action::Event(Event::Forfeit),
+            Key::Char('z') => Action::Event(Event::Hold),
+            Key::Char('x') => Action::Event(Event::RotateLeft),
+            Key::Char('c') | Key::Up => Action::Event(Event::RotateRight),
+            Key::Char('s') => Action::Event(Event::Rotate180),
+            Key::Char(' ') => Action::Event(Event::HardDrop),
+            Key::Char('h') => Action::ToggleHint,
+            Key::Char('u') => Action::Event(Event::Undo),
+            Key::Char('y') => Action::Event(Event::Redo),
+            _ => return,
+        };
+        self.pending.push_back(action);
+    }
+}
+
+impl InputSource for TermInput {
+    fn poll(&mut self, now_ms: u64) -> Option<Action> {
+        if self.pending.is_empty() {
+            while let Ok(bytes) = self.receiver.try_recv() {
+                for key in parse_keys(&bytes) {
+                    self.press(key, now_ms);
+                }
+            }
+            for key in [Key::Left, Key::Right, Key::Down] {
+                if let Some((held_key, _, end)) = self.held_key(key) {
+                    if held_key.release(now_ms) {
+                        self.pending.push_back(Action::Event(end));
+                    }
+                }
+            }
+        }
+        self.pending.pop_front()
+    }
+}
+
+/// 系统时钟
+pub struct StdClock {
+    start: Instant,
+}
+
+impl Default for StdClock {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Clock for StdClock {
+    fn now_ms(&mut self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+
+    fn sleep_ms(&mut self, ms: u64) {
+        thread::sleep(Duration::from_millis(ms));
+    }
+}
+
+/// 把画面输出到标准输出
+#[derive(Default)]
+pub struct TermRenderer;
+
+impl Renderer for TermRenderer {
+    fn render(&mut self, game: &mut ReplayGame, hint: Option<&Piece>) {
+        let mut stdout = io::stdout().lock();
+        let _ = stdout.write_all(render(game, hint).as_bytes());
+        let _ = stdout.flush();
+    }
+}
+
+/// 把录像写入指定的文件，不支持存档
+#[derive(Default)]
+pub struct FileStorage {
+    pub replay_path: Option<String>,
+}
+
+impl Storage for FileStorage {
+    fn save_replay(&mut self, replay: &Replay) -> Result<(), ()> {
+        match &self.replay_path {
+            Some(path) => fs::write(path, replay.encode()).map_err(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    fn save_game(&mut self, _game: &ReplayGame, _replay: &Replay) -> Result<(), ()> {
+        Err(())
+    }
+
+    fn take_saved_game(&mut self) -> Option<(ReplayGame, Replay)> {
+        None
+    }
+
+    fn has_saved_game(&mut self) -> bool {
+        false
+    }
+}
+
+/// 画面上的一个格子，占两个字符宽
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Cell {
+    Empty,
+    Block(Rgb),
+    Border,
+    /// 提示的落点
+    Hint(Rgb),
+    /// finesse 错误，一个格子两个
+    Pips(usize, Rgb),
+}
+
+/// 把游戏画面渲染为 ANSI 字符串：光标先回到左上角，每行以 `\r\n` 结束
+///
+/// 与 UEFI 版的 render 布局相同：左边是 Hold 和 finesse 错误，中间是版面，右边是预览
+///
+/// * `hint`: 提示的落点，画成空心的方块
+pub fn render<G>(game: &mut Game<PreviewGenerator<usize, G>>, hint: Option<&Piece>) -> String
+    where
+        G: Iterator<Item=usize>
+{
+    let width = game.board.board.width;
+    let height = game.board.board.height;
+    // 列 0 对应版面的 x = -6
+    let columns = width + 12;
+    let mut grid = vec![vec![Cell::Empty; columns]; height + 1];
+    let put = |grid: &mut Vec<Vec<Cell>>, x: isize, y: isize, cell: Cell| {
+        if let (Some(column), Ok(y)) = (x.checked_add(6).and_then(|x| usize::try_from(x).ok()), usize::try_from(y)) {
+            if let Some(target) = grid.get_mut(y).and_then(|row| row.get_mut(column)) {
+                *target = cell;
+            }
+        }
+    };
+
+    for y in 0..height {
+        for x in 0..width {
+            if let Some(Some(color_id)) = game.board.board.get_cell(x, y) {
+                put(&mut grid, x as isize, y as isize, Cell::Block(COLOR_TABLE[*color_id]));
+            }
+        }
+    }
+    if let Some(current_piece) = &game.board.current_piece {
+        if let Some(piece_data) = game.board.piece_data.get(current_piece.typ) {
+            let cells = &piece_data.orientation()[current_piece.orientation];
+            // 半透明颜色
+            let color = COLOR_TABLE[piece_data.color_id];
+            let drop = game.board.test_fast_drop();
+            for (x_offset, y_offset) in cells {
+                put(&mut grid, current_piece.position.0 + *x_offset as isize, current_piece.position.1 + drop + *y_offset as isize, Cell::Block(Rgb::new(color.r / 2, color.g / 2, color.b / 2)));
+            }
+            // 提示落点，只画轮廓
+            if let Some(hint) = hint {
+                if let Some(hint_data) = game.board.piece_data.get(hint.typ) {
+                    for (x_offset, y_offset) in &hint_data.orientation()[hint.orientation] {
+                        put(&mut grid, hint.position.0 + *x_offset as isize, hint.position.1 + *y_offset as isize, Cell::Hint(COLOR_TABLE[hint_data.color_id]));
+                    }
+                }
+            }
+            for (x_offset, y_offset) in cells {
+                put(&mut grid, current_piece.position.0 + *x_offset as isize, current_piece.position.1 + *y_offset as isize, Cell::Block(COLOR_TABLE[piece_data.color_id]));
+            }
+        }
+    }
+
+    let mut y_index = 2;
+    for typ in game.rng.preview() {
+        if let Some(piece_data) = game.board.piece_data.get(*typ) {
+            let x_index = width as isize + 1 + (4 - piece_data.initial_width as isize) / 2;
+            for (x_offset, y_offset) in &piece_data.orientation()[piece_data.spawn_orientation] {
+                put(&mut grid, x_index + *x_offset as isize, y_index + *y_offset as isize, Cell::Block(COLOR_TABLE[piece_data.color_id]));
+            }
+            y_index += 3;
+        }
+    }
+
+    if let Some(piece_data) = game.hold.and_then(|typ| game.board.piece_data.get(typ)) {
+        for (x_offset, y_offset) in &piece_data.orientation()[piece_data.spawn_orientation] {
+            put(&mut grid, -5 + *x_offset as isize, 2 + *y_offset as isize, Cell::Block(COLOR_TABLE[piece_data.color_id]));
+        }
+    }
+
+    // finesse 错误计数，训练模式下为黄色
+    let color = if game.config.finesse_training {
+        Rgb::new(227, 159, 2)
+    } else {
+        Rgb::new(215, 15, 55)
+    };
+    let faults = game.finesse_faults.min(40);
+    for i in 0..faults.div_ceil(2) {
+        put(&mut grid, -5 + (i % 4) as isize, 5 + (i / 4) as isize, Cell::Pips((faults - i * 2).min(2), color));
+    }
+
+    for y in 2..=height {
+        put(&mut grid, -1, y as isize, Cell::Border);
+        put(&mut grid, width as isize, y as isize, Cell::Border);
+    }
+    for x in 0..width {
+        put(&mut grid, x as isize, height as isize, Cell::Border);
+    }
+
+    let mut out = String::from("\x1b[H");
+    for row in &grid {
+        for cell in row {
+            let _ = match cell {
+                Cell::Empty => write!(out, "\x1b[0m  "),
+                Cell::Block(color) => write!(out, "\x1b[48;2;{};{};{}m  ", color.r, color.g, color.b),
+                Cell::Border => write!(out, "\x1b[48;2;255;255;255m  "),
+                Cell::Hint(color) => write!(out, "\x1b[0m\x1b[38;2;{};{};{}m[]", color.r, color.g, color.b),
+                Cell::Pips(count, color) => write!(out, "\x1b[0m\x1b[38;2;{};{};{}m{}", color.r, color.g, color.b, if *count == 2 { "••" } else { "• " }),
+            };
+        }
+        out.push_str("\x1b[0m\x1b[K\r\n");
+    }
+    let _ = write!(
+        out,
+        "Score {}  Lines {}  Pieces {}  Finesse {}{}\x1b[K\r\n",
+        game.score,
+        game.lines,
+        game.pieces,
+        game.finesse_faults,
+        if game.config.practice { "  [practice]" } else { "" },
+    );
+    out
+}
@@ -0,0 +1,106 @@
+use std::sync::mpsc;
+
+use tetris::{run_game, Action, Config, Event, GameEnd, InputSource, ManualClock, MemoryStorage, NullRenderer, Replay, ReplayGame, ScriptedInput, Storage, DEFAULT_PIECE_SET, TICK_MS};
+use tetris_host::term::{TermInput, RELEASE_MS};
+
+fn new_game(seed: u32) -> (ReplayGame, Replay) {
+    let replay = Replay::new(seed, DEFAULT_PIECE_SET, 10, 22, 0, 5, Config::default());
+    (replay.new_game(true).unwrap(), replay)
+}
+
+/// 每隔 100 毫秒硬降一次，最后退出
+fn hard_drops(count: u64) -> Vec<(u64, Action)> {
+    let mut actions: Vec<_> = (1..=count).map(|i| (i * 100, Action::Event(Event::HardDrop))).collect();
+    actions.push(((count + 1) * 100, Action::Quit));
+    actions
+}
+
+#[test]
+fn loop_ticks_renders_and_saves_the_replay() {
+    let (mut game, mut replay) = new_game(1);
+    let mut input = ScriptedInput::new(hard_drops(5));
+    let mut clock = ManualClock::default();
+    let mut renderer = NullRenderer::default();
+    let mut storage = MemoryStorage::default();
+    let end = run_game(&mut game, &mut replay, &mut input, &mut clock, &mut renderer, &mut storage);
+    assert_eq!(end, GameEnd::Quit);
+    assert_eq!(game.pieces, 5);
+    assert_eq!(input.remaining(), 0);
+    assert_eq!(clock.now_ms, 600);
+    // 600 毫秒时先处理输入退出，最后一个 tick 在 585 毫秒
+    assert_eq!(renderer.frames as u64, 599 / TICK_MS);
+    assert_eq!(game.current_tick, renderer.frames);
+    assert_eq!(storage.replays.len(), 1);
+    assert!(storage.replays[0].verify());
+    assert!(!storage.has_saved_game());
+}
+
+#[test]
+fn forfeit_ends_the_game() {
+    let (mut game, mut replay) = new_game(1);
+    let mut input = ScriptedInput::new(vec![(50, Action::Event(Event::Forfeit))]);
+    let mut storage = MemoryStorage::default();
+    let end = run_game(&mut game, &mut replay, &mut input, &mut ManualClock::default(), &mut NullRenderer::default(), &mut storage);
+    assert_eq!(end, GameEnd::GameOver);
+    assert!(storage.replays[0].verify());
+}
+
+#[test]
+fn suspend_and_resume() {
+    // 不暂停的一局
+    let (mut expected, mut replay) = new_game(3);
+    let mut storage = MemoryStorage::default();
+    run_game(&mut expected, &mut replay, &mut ScriptedInput::new(hard_drops(8)), &mut ManualClock::default(), &mut NullRenderer::default(), &mut storage);
+    storage.replays.clear();
+
+    // 同样的输入，中途暂停
+    let (mut game, mut replay) = new_game(3);
+    let actions = hard_drops(8);
+    let (first, second) = actions.split_at(4);
+    let mut input = ScriptedInput::new(first.iter().copied().chain([(450, Action::Suspend)]).collect());
+    let end = run_game(&mut game, &mut replay, &mut input, &mut ManualClock::default(), &mut NullRenderer::default(), &mut storage);
+    assert_eq!(end, GameEnd::Suspended);
+    assert!(storage.replays.is_empty());
+    assert!(storage.has_saved_game());
+
+    let (mut game, mut replay) = storage.take_saved_game().unwrap();
+    assert!(!storage.has_saved_game());
+    // 继续时时钟从暂停的时间开始
+    let mut clock = ManualClock { now_ms: 450 };
+    let end = run_game(&mut game, &mut replay, &mut ScriptedInput::new(second.to_vec()), &mut clock, &mut NullRenderer::default(), &mut storage);
+    assert_eq!(end, GameEnd::Quit);
+    let resumed = storage.replays.pop().unwrap();
+    assert!(resumed.verify());
+    // 暂停时还没到的 tick 不会补上，所以只比较版面和方块
+    assert_eq!(game.board.board.to_text(), expected.board.board.to_text());
+    assert_eq!((game.pieces, game.hold), (expected.pieces, expected.hold));
+    assert_eq!(resumed.events.iter().filter(|(_, event)| *event != Event::Tick).count(), 8);
+}
+
+#[test]
+fn terminal_input_taps_and_holds() {
+    let (sender, receiver) = mpsc::channel();
+    let mut input = TermInput::new(receiver);
+    let mut drain = |now_ms| {
+        let mut actions = Vec::new();
+        while let Some(action) = input.poll(now_ms) {
+            actions.push(action);
+        }
+        actions
+    };
+
+    sender.send(b"\x1b[D".to_vec()).unwrap();
+    assert_eq!(drain(0), [Action::Event(Event::MoveLeftBegin), Action::Event(Event::MoveLeftEnd)]);
+
+    // 自动重复：第二次重复开始按住，停止后抬起
+    sender.send(b"\x1b[D".to_vec()).unwrap();
+    assert_eq!(drain(500), [Action::Event(Event::MoveLeftBegin), Action::Event(Event::MoveLeftEnd)]);
+    sender.send(b"\x1b[D".to_vec()).unwrap();
+    assert_eq!(drain(530), [Action::Event(Event::MoveLeftBegin)]);
+    sender.send(b"\x1b[D".to_vec()).unwrap();
+    assert_eq!(drain(560), []);
+    assert_eq!(drain(560 + RELEASE_MS), [Action::Event(Event::MoveLeftEnd)]);
+
+    sender.send(b" hq".to_vec()).unwrap();
+    assert_eq!(drain(1000), [Action::Event(Event::HardDrop), Action::ToggleHint, Action::Quit]);
+}
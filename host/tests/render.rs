@@ -0,0 +1,185 @@
+use tetris::{draw_text, glyph, render_game, Config, Event, Replay, ReplayGame, Rgb, COLOR_TABLE, DEFAULT_PIECE_SET, GLYPH_HEIGHT, GLYPH_SPACING, GLYPH_WIDTH};
+use tetris_host::image::Image;
+
+/// 160x104 的图片每格 4 像素，版面左上角在 (60, 8)
+const WIDTH: usize = 160;
+const HEIGHT: usize = 104;
+const CELL: usize = 4;
+const START_X: usize = 60;
+const START_Y: usize = 8;
+
+fn new_game(seed: u32) -> ReplayGame {
+    Replay::new(seed, DEFAULT_PIECE_SET, 10, 22, 0, 5, Config::default()).new_game(false).unwrap()
+}
+
+/// 取每格中心的颜色，转换为字符：方块为名称，半透明（影子）为小写，空为 `.`，边框为 `|`
+///
+/// 列从版面左边第 5 格（Hold）到右边第 5 格（预览）
+fn snapshot(image: &Image) -> String {
+    let mut lines = Vec::new();
+    for y in 0..22 {
+        let mut line = String::new();
+        for x in -5..15isize {
+            let px = (START_X as isize + x * CELL as isize + CELL as isize / 2) as usize;
+            let py = START_Y + y * CELL + CELL / 2;
+            let color = image.pixel(px, py).unwrap();
+            let c = if color == Rgb::new(0, 0, 0) {
+                '.'
+            } else if color == Rgb::new(255, 255, 255) {
+                '|'
+            } else if let Some(i) = COLOR_TABLE.iter().position(|c| *c == color) {
+                "IJLOSTZ#".as_bytes()[i] as char
+            } else if let Some(i) = COLOR_TABLE.iter().position(|c| Rgb::new(c.r / 2, c.g / 2, c.b / 2) == color) {
+                "ijlostz#".as_bytes()[i] as char
+            } else {
+                '?'
+            };
+            line.push(c);
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+fn render(game: &mut ReplayGame) -> Image {
+    let mut image = Image::new(WIDTH, HEIGHT);
+    render_game(game, &mut image, None, None);
+    image
+}
+
+#[test]
+fn playfield_ghost_preview_and_hold() {
+    let mut game = new_game(1);
+    game.on_event(Event::HardDrop).unwrap();
+    game.on_event(Event::Hold).unwrap();
+    game.on_event(Event::MoveLeftBegin).unwrap();
+    game.on_event(Event::MoveLeftEnd).unwrap();
+    game.on_event(Event::HardDrop).unwrap();
+    game.on_event(Event::RotateRight).unwrap();
+    let image = render(&mut game);
+    let expected = "\
+.........JJ.........
+.........J..........
+..L......J......ZZ..
+LLL..............ZZ.
+....................
+....................
+................IIII
+....................
+.................SS.
+................SS..
+....................
+....................
+................IIII
+....................
+.................T..
+................TTT.
+.........jj.........
+.........j..........
+........Tj..........
+.......TTT..........
+.........OO.........
+.........OO.........";
+    assert_eq!(snapshot(&image), expected);
+    // 版面边框是 1 像素宽的白线，从第 2 行开始
+    assert_eq!(image.pixel(START_X - 1, START_Y + 2 * CELL), Some(Rgb::new(255, 255, 255)));
+    assert_eq!(image.pixel(START_X - 1, START_Y + 2 * CELL - 1), Some(Rgb::new(0, 0, 0)));
+    assert_eq!(image.pixel(START_X + 10 * CELL + 1, START_Y + 21 * CELL), Some(Rgb::new(255, 255, 255)));
+}
+
+#[test]
+fn empty_hold_box() {
+    let mut game = new_game(1);
+    let image = render(&mut game);
+    let hold_rows: Vec<String> = snapshot(&image).lines().map(|line| line.chars().take(4).collect()).collect();
+    assert!(hold_rows.iter().all(|row| row.chars().all(|c| c == '.')));
+}
+
+/// 按字形识别一行文字，非黑色的点视为笔画
+fn read_text(image: &Image, (x, y): (usize, usize), scale: usize, len: usize) -> String {
+    (0..len).map(|i| {
+        let left = x + i * (GLYPH_WIDTH + GLYPH_SPACING) * scale;
+        let mut rows = [0u8; GLYPH_HEIGHT];
+        for (row_index, row) in rows.iter_mut().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if image.pixel(left + column * scale, y + row_index * scale) != Some(Rgb::new(0, 0, 0)) {
+                    *row |= 1 << (GLYPH_WIDTH - 1 - column);
+                }
+            }
+        }
+        (' '..='_').find(|c| *glyph(*c) == rows).unwrap_or('~')
+    }).collect()
+}
+
+#[test]
+fn glyphs_are_scaled_and_spaced() {
+    let mut image = Image::new(24, 14);
+    let width = draw_text(&mut image, (0, 0), 2, "1l", Rgb::new(255, 255, 255));
+    assert_eq!(width, 22);
+    let rows: Vec<String> = (0..7).map(|y| (0..12).map(|x| if image.pixel(x * 2, y * 2) == Some(Rgb::new(255, 255, 255)) { '#' } else { '.' }).collect()).collect();
+    // 小写按大写显示
+    assert_eq!(rows, [
+        "..#...#.....",
+        ".##...#.....",
+        "..#...#.....",
+        "..#...#.....",
+        "..#...#.....",
+        "..#...#.....",
+        ".###..#####.",
+    ]);
+    assert_eq!(image.pixel(1, 1), Some(Rgb::new(0, 0, 0)));
+    assert_eq!(image.pixel(5, 1), Some(Rgb::new(255, 255, 255)));
+}
+
+#[test]
+fn hud_shows_labels_and_statistics() {
+    let mut game = new_game(1);
+    for _ in 0..3 {
+        game.on_event(Event::HardDrop).unwrap();
+    }
+    for _ in 0..100 {
+        game.on_event(Event::Tick).unwrap();
+    }
+    // 640x480 时每格 18 像素，文字放大 2 倍，每行文字在一格内垂直居中
+    let mut image = Image::new(640, 480);
+    render_game(&mut game, &mut image, None, Some(1234));
+    let (cell, scale, start_x, start_y) = (18, 2, 230, 42);
+    let left = start_x - 5 * cell;
+    let row_y = |row: usize| start_y + row * cell + 2;
+    let read = |x: usize, row: usize, len: usize| read_text(&image, (x, row_y(row)), scale, len);
+    assert_eq!(read(left, 1, 4), "HOLD");
+    assert_eq!(read(start_x + 11 * cell, 1, 4), "NEXT");
+    assert_eq!(read(left, 4, 7), "FINESSE");
+    assert_eq!(read(left, 11, 5), "SCORE");
+    let score = game.score.to_string();
+    assert_eq!(read(left, 12, score.len() + 1), score + " ");
+    assert_eq!(read(left, 13, 5), "LINES");
+    assert_eq!(read(left, 14, 2), "0 ");
+    assert_eq!(read(left, 15, 6), "PIECES");
+    assert_eq!(read(left, 16, 2), "3 ");
+    // 100 个 tick，每个 15 毫秒
+    assert_eq!(read(left, 17, 4), "TIME");
+    assert_eq!(read(left, 18, 6), "0:01.5");
+    let seed_x = start_x + (10 * cell - 9 * (GLYPH_WIDTH + GLYPH_SPACING) * scale + GLYPH_SPACING * scale) / 2;
+    assert_eq!(read_text(&image, (seed_x, start_y + 22 * cell + cell / 2), scale, 9), "SEED 1234");
+}
+
+#[test]
+fn ppm_and_bmp_encoding() {
+    let mut image = Image::new(2, 2);
+    image.pixels[0] = Rgb::new(1, 2, 3);
+    image.pixels[3] = Rgb::new(4, 5, 6);
+
+    let ppm = image.to_ppm();
+    assert!(ppm.starts_with(b"P6\n2 2\n255\n"));
+    assert_eq!(&ppm[ppm.len() - 12..], &[1, 2, 3, 0, 0, 0, 0, 0, 0, 4, 5, 6]);
+
+    let bmp = image.to_bmp();
+    // 每行 6 字节补齐到 8 字节
+    assert_eq!(bmp.len(), 54 + 16);
+    assert_eq!(&bmp[0..2], b"BM");
+    assert_eq!(u32::from_le_bytes(bmp[2..6].try_into().unwrap()), 70);
+    // 行从下往上存放，像素为 BGR
+    assert_eq!(&bmp[54..62], &[0, 0, 0, 6, 5, 4, 0, 0]);
+    assert_eq!(&bmp[62..70], &[3, 2, 1, 0, 0, 0, 0, 0]);
+}
@@ -0,0 +1,14 @@
+use tetris_host::sim::{simulate, SimEndReason, SimOptions, CSV_HEADER};
+
+#[test]
+fn simulation_is_deterministic() {
+    let options = SimOptions {
+        max_pieces: 50,
+        ..SimOptions::default()
+    };
+    let result = simulate(7, &options);
+    assert_eq!(result, simulate(7, &options));
+    assert_eq!(result.end_reason, SimEndReason::PieceLimit);
+    assert_eq!(result.pieces, 50);
+    assert_eq!(result.to_csv().split(',').count(), CSV_HEADER.split(',').count());
+}
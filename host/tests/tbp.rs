@@ -0,0 +1,81 @@
+use std::process::Command;
+
+use tetris::{new_default_piece_data, Board, Piece};
+use tetris_host::tbp::{board_from_tbp, board_to_tbp, location_to_piece, piece_to_location, FrontendMessage, Location, Move, Orientation, Spin, TBP_BOARD_HEIGHT};
+
+#[test]
+fn adapter_plays_against_mock_bot() {
+    let output = Command::new(env!("CARGO_BIN_EXE_tbp"))
+        .args(["--seed", "42", "--pieces", "100", env!("CARGO_BIN_EXE_mock_tbp_bot")])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("pieces 100"), "{}", stdout);
+    assert!(stdout.contains("end PieceLimit"), "{}", stdout);
+}
+
+#[test]
+fn location_round_trip() {
+    let piece_data = new_default_piece_data();
+    for typ in 0..piece_data.len() {
+        for orientation in 0..4 {
+            let piece = Piece {
+                typ,
+                position: (3, 10),
+                orientation,
+            };
+            let location = piece_to_location(&piece_data, &piece, 22).unwrap();
+            assert_eq!(location.kind, piece_data[typ].name);
+            let back = location_to_piece(&piece_data, &location, 22).unwrap();
+            assert_eq!((back.typ, back.position, back.orientation), (typ, piece.position, orientation));
+        }
+    }
+}
+
+#[test]
+fn spawn_location_matches_srs_center() {
+    let piece_data = new_default_piece_data();
+    // T 方块在默认位置进场，中心在第 4 列，版面最上面往下第 2 行
+    let piece = Piece {
+        typ: 5,
+        position: (3, 0),
+        orientation: 0,
+    };
+    let location = piece_to_location(&piece_data, &piece, 22).unwrap();
+    assert_eq!(location, Location {
+        kind: "T".to_string(),
+        orientation: Orientation::North,
+        x: 4,
+        y: 20,
+    });
+}
+
+#[test]
+fn board_round_trip() {
+    let board = Board::from_text("..........\nT.........\nIJLOSZG.GG\n").unwrap();
+    let cells = board_to_tbp(&board);
+    assert_eq!(cells.len(), TBP_BOARD_HEIGHT);
+    assert_eq!(cells[0][0].as_deref(), Some("I"));
+    assert_eq!(cells[0][7], None);
+    assert_eq!(cells[1][0].as_deref(), Some("T"));
+    assert!(cells[3..].iter().flatten().all(|cell| cell.is_none()));
+    assert_eq!(board_from_tbp(&cells, board.width, board.height).to_text(), board.to_text());
+}
+
+#[test]
+fn message_format() {
+    let mv = Move {
+        location: Location {
+            kind: "T".to_string(),
+            orientation: Orientation::East,
+            x: 1,
+            y: 2,
+        },
+        spin: Spin::None,
+    };
+    let json = serde_json::to_string(&FrontendMessage::Play { mv }).unwrap();
+    assert_eq!(json, r#"{"type":"play","move":{"location":{"type":"T","orientation":"east","x":1,"y":2},"spin":"none"}}"#);
+    let message: FrontendMessage = serde_json::from_str(r#"{"type":"rules","randomizer":{"type":"seven_bag"}}"#).unwrap();
+    assert_eq!(message, FrontendMessage::Rules {});
+}
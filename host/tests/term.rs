@@ -0,0 +1,41 @@
+use tetris::{Config, Event, Replay, DEFAULT_PIECE_SET};
+use tetris_host::term::{parse_keys, render, HeldKey, Key, Press, RELEASE_MS};
+
+#[test]
+fn parse_arrow_keys_and_characters() {
+    assert_eq!(
+        parse_keys(b"\x1b[Dx\x1b[C \x1bZ"),
+        vec![Key::Left, Key::Char('x'), Key::Right, Key::Char(' '), Key::Escape, Key::Char('z')],
+    );
+}
+
+#[test]
+fn held_key_from_auto_repeat() {
+    let mut key = HeldKey::default();
+    // 单独的按下是点按
+    assert_eq!(key.press(0), Press::Tap);
+    assert!(!key.release(1000));
+    assert_eq!(key.press(1000), Press::Tap);
+    // 系统开始自动重复
+    assert_eq!(key.press(1500), Press::Tap);
+    assert_eq!(key.press(1530), Press::Hold);
+    assert_eq!(key.press(1560), Press::Repeat);
+    assert!(key.is_held());
+    assert!(!key.release(1560 + RELEASE_MS - 1));
+    assert!(key.release(1560 + RELEASE_MS));
+    assert!(!key.is_held());
+    assert_eq!(key.press(3000), Press::Tap);
+}
+
+#[test]
+fn render_has_one_line_per_row() {
+    let replay = Replay::new(1, DEFAULT_PIECE_SET, 10, 22, 0, 5, Config::default());
+    let mut game = replay.new_game(false).unwrap();
+    game.on_event(Event::HardDrop).unwrap();
+    game.on_event(Event::Tick).unwrap();
+    let frame = render(&mut game, None);
+    assert!(frame.starts_with("\x1b[H"));
+    // 22 行版面、底边和状态行
+    assert_eq!(frame.matches("\r\n").count(), 24);
+    assert!(frame.contains("Pieces 1"));
+}
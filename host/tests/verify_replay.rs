@@ -0,0 +1,84 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use tetris::{Bot, BotConfig, Config, Event, Replay, DEFAULT_PIECE_SET, MAX_BOARD_WIDTH};
+
+/// 用内置机器人录制一局
+fn record(seed: u32) -> Replay {
+    let mut replay = Replay::new(seed, DEFAULT_PIECE_SET, 10, 22, 0, 5, Config::default());
+    let mut game = replay.new_game(true).unwrap();
+    let mut bot = Bot::new(BotConfig {
+        pps: 5.0,
+        depth: 1,
+        ..BotConfig::default()
+    });
+    while game.pieces < 60 {
+        bot.step(&mut game).unwrap();
+    }
+    game.on_event(Event::Forfeit).unwrap_err();
+    replay.finish(&game);
+    replay
+}
+
+fn run(name: &str, replay: &Replay) -> (bool, String) {
+    let path: PathBuf = std::env::temp_dir().join(format!("tetris-verify-{}-{}.rpl", std::process::id(), name));
+    fs::write(&path, replay.encode()).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_verify_replay")).arg(&path).output().unwrap();
+    fs::remove_file(&path).unwrap();
+    (output.status.success(), String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[test]
+fn accepts_valid_replay() {
+    let replay = record(1);
+    let result = replay.result.unwrap();
+    assert!(result.score > 0);
+    let (ok, stdout) = run("valid", &replay);
+    assert!(ok, "{}", stdout);
+    assert!(stdout.contains(&format!("score    {}", result.score)), "{}", stdout);
+}
+
+#[test]
+fn rejects_wrong_claim() {
+    let mut replay = record(2);
+    if let Some(result) = &mut replay.result {
+        result.score += 100;
+    }
+    let (ok, stdout) = run("claim", &replay);
+    assert!(!ok);
+    assert!(stdout.contains("MISMATCH"), "{}", stdout);
+}
+
+#[test]
+fn rejects_desync() {
+    let mut replay = record(3);
+    let index = replay.events.iter().position(|(_, event)| *event == Event::HardDrop).unwrap();
+    replay.events.remove(index);
+    let (ok, stdout) = run("desync", &replay);
+    assert!(!ok);
+    assert!(stdout.contains("DESYNC"), "{}", stdout);
+}
+
+#[test]
+fn rejects_hostile_header() {
+    let valid = Replay::new(4, DEFAULT_PIECE_SET, 10, 22, 0, 5, Config::default());
+    assert!(valid.new_game(false).is_some());
+    assert_eq!(Replay::decode(&valid.encode()), Some(valid.clone()));
+    let hostile = [
+        ("width-0", Replay { width: 0, ..valid.clone() }),
+        ("width-65", Replay { width: MAX_BOARD_WIDTH + 1, ..valid.clone() }),
+        ("height-0", Replay { height: 0, ..valid.clone() }),
+        ("height-huge", Replay { height: usize::MAX / 2, ..valid.clone() }),
+        ("start-y", Replay { start_y: isize::MIN, ..valid.clone() }),
+        ("preview", Replay { preview_count: usize::MAX, ..valid.clone() }),
+        ("end-tick", Replay { end_tick: usize::MAX, ..valid.clone() }),
+    ];
+    for (name, replay) in hostile {
+        assert!(replay.new_game(false).is_none(), "{}", name);
+        assert!(Replay::decode(&replay.encode()).is_none(), "{}", name);
+        let (ok, stdout) = run(name, &replay);
+        assert!(!ok, "{}", name);
+        assert!(stdout.contains("invalid parameters"), "{}: {}", name, stdout);
+    }
+}
@@ -0,0 +1,169 @@
+//! UEFI 前端：共用游戏循环需要的输入、时钟、画面和存储
+
+use tetris::{Action, Clock, Control, Event, InputSource, KeyBindings, KeyCode, Piece, Renderer, Replay, ReplayGame, Storage, render_game};
+use uefi::prelude::*;
+use uefi::proto::console::gop::GraphicsOutput;
+use uefi::proto::console::text::{Input, Key, ScanCode};
+use uefi::proto::media::fs::SimpleFileSystem;
+use uefi::table::boot::BootServices;
+
+use crate::{replays, saves, Buffer};
+
+/// UEFI 按键转为平台无关的按键，不支持的按键返回 None
+pub fn key_code(key: Key) -> Option<KeyCode> {
+    match key {
+        Key::Printable(c) => Some(KeyCode::from_char(char::from(c))),
+        Key::Special(ScanCode::UP) => Some(KeyCode::Up),
+        Key::Special(ScanCode::DOWN) => Some(KeyCode::Down),
+        Key::Special(ScanCode::LEFT) => Some(KeyCode::Left),
+        Key::Special(ScanCode::RIGHT) => Some(KeyCode::Right),
+        Key::Special(ScanCode::ESCAPE) => Some(KeyCode::Escape),
+        Key::Special(_) => None,
+    }
+}
+
+/// 键盘输入。只能检测按键按下，左右移动每次按下移动一格
+pub struct KeyboardInput<'a> {
+    input: &'a mut Input,
+    bindings: KeyBindings,
+    /// 一个按键对应两个动作时，第二个动作留到下一次
+    pending: Option<Action>,
+}
+
+impl<'a> KeyboardInput<'a> {
+    pub fn new(input: &'a mut Input, bindings: KeyBindings) -> Self {
+        Self {
+            input,
+            bindings,
+            pending: None,
+        }
+    }
+}
+
+impl InputSource for KeyboardInput<'_> {
+    fn poll(&mut self, _now_ms: u64) -> Option<Action> {
+        if let Some(action) = self.pending.take() {
+            return Some(action);
+        }
+        loop {
+            let key = self.input.read_key().ok()??;
+            let control = match key_code(key).and_then(|key| self.bindings.control(key)) {
+                Some(control) => control,
+                None => continue,
+            };
+            let action = match control {
+                Control::MoveLeft => {
+                    self.pending = Some(Action::Event(Event::MoveLeftEnd));
+                    Action::Event(Event::MoveLeftBegin)
+                }
+                Control::MoveRight => {
+                    self.pending = Some(Action::Event(Event::MoveRightEnd));
+                    Action::Event(Event::MoveRightBegin)
+                }
+                Control::SoftDrop => Action::Event(Event::SoftDropFast),
+                Control::HardDrop => Action::Event(Event::HardDrop),
+                Control::RotateLeft => Action::Event(Event::RotateLeft),
+                Control::RotateRight => Action::Event(Event::RotateRight),
+                Control::Rotate180 => Action::Event(Event::Rotate180),
+                Control::Hold => Action::Event(Event::Hold),
+                Control::Forfeit => Action::Event(Event::Forfeit),
+                Control::Hint => Action::ToggleHint,
+                Control::Suspend => Action::Suspend,
+                Control::Undo => Action::Event(Event::Undo),
+                Control::Redo => Action::Event(Event::Redo),
+            };
+            return Some(action);
+        }
+    }
+}
+
+/// 用 Stall 计时的时钟。只累计等待的时间，不计处理输入和渲染花费的时间
+pub struct StallClock<'a> {
+    boot_services: &'a BootServices,
+    now_ms: u64,
+}
+
+impl<'a> StallClock<'a> {
+    pub fn new(boot_services: &'a BootServices) -> Self {
+        Self {
+            boot_services,
+            now_ms: 0,
+        }
+    }
+}
+
+impl Clock for StallClock<'_> {
+    fn now_ms(&mut self) -> u64 {
+        self.now_ms
+    }
+
+    fn sleep_ms(&mut self, ms: u64) {
+        self.boot_services.stall(ms as usize * 1_000);
+        self.now_ms += ms;
+    }
+}
+
+/// GOP 屏幕：先画到缓冲区，再一次性复制到屏幕
+pub struct Screen<'a> {
+    gop: &'a mut GraphicsOutput,
+    buffer: Buffer,
+    /// 这一局的随机种子，显示在版面下方
+    seed: u32,
+}
+
+impl<'a> Screen<'a> {
+    pub fn new(gop: &'a mut GraphicsOutput, seed: u32) -> Self {
+        let (width, height) = gop.current_mode_info().resolution();
+        Self {
+            gop,
+            buffer: Buffer::new(width, height),
+            seed,
+        }
+    }
+}
+
+impl Renderer for Screen<'_> {
+    fn render(&mut self, game: &mut ReplayGame, hint: Option<&Piece>) {
+        render_game(game, &mut self.buffer, hint, Some(self.seed));
+        self.buffer.blit(self.gop).unwrap();
+    }
+}
+
+/// 启动卷：录像保存在 `\replays\`，存档保存在 `tetris.sav`。没有文件系统时什么也不保存
+pub struct BootVolume<'a> {
+    system_table: &'a SystemTable<Boot>,
+    fs: Option<&'a mut SimpleFileSystem>,
+}
+
+impl<'a> BootVolume<'a> {
+    pub fn new(system_table: &'a SystemTable<Boot>, fs: Option<&'a mut SimpleFileSystem>) -> Self {
+        Self {
+            system_table,
+            fs,
+        }
+    }
+}
+
+impl Storage for BootVolume<'_> {
+    fn save_replay(&mut self, replay: &Replay) -> Result<(), ()> {
+        let fs = self.fs.as_deref_mut().ok_or(())?;
+        replays::save_replay(self.system_table, fs, replay).map_err(|_| ())
+    }
+
+    fn save_game(&mut self, game: &ReplayGame, replay: &Replay) -> Result<(), ()> {
+        let fs = self.fs.as_deref_mut().ok_or(())?;
+        saves::save_game(fs, game, replay).map_err(|_| ())
+    }
+
+    fn take_saved_game(&mut self) -> Option<(ReplayGame, Replay)> {
+        let fs = self.fs.as_deref_mut()?;
+        // 无法解码的存档（例如更新的版本）不删除
+        let saved = saves::load_saved_game(fs).ok().flatten()?;
+        let _ = saves::delete_saved_game(fs);
+        Some(saved)
+    }
+
+    fn has_saved_game(&mut self) -> bool {
+        self.fs.as_deref_mut().is_some_and(saves::has_saved_game)
+    }
+}
@@ -5,11 +5,26 @@ extern crate alloc;
 
 use alloc::vec;
 use alloc::vec::Vec;
-use tetris::{Config, Event, ExtendedBoard, Game, new_default_piece_data, PreviewGenerator, SevenBagGenerator};
+use tetris::{Bot, BotConfig, Config, Control, DEFAULT_PIECE_SET, ExtendedBoard, Framebuffer, Game, KeyBindings, KeyCode, Menu, MenuInput, MenuItem, new_default_piece_data, PreviewGenerator, render_game, render_menu, render_title, Replay, ReplayGame, Rgb, Setting, SevenBagGenerator, Storage};
 use uefi::prelude::*;
 use uefi::proto::console::gop::{BltOp, BltPixel, BltRegion, GraphicsOutput};
-use uefi::proto::console::text::{Input, Key, ScanCode};
+use uefi::proto::console::text::Input;
+use uefi::proto::device_path::DevicePath;
+use uefi::proto::loaded_image::LoadedImage;
+use uefi::proto::media::fs::SimpleFileSystem;
 use uefi::proto::rng::Rng;
+use uefi::table::boot::{BootServices, ScopedProtocol};
+
+use crate::frontend::{BootVolume, KeyboardInput, Screen, StallClock};
+
+mod frontend;
+mod replays;
+mod saves;
+
+/// 每个 tick 的毫秒数
+const TICK_MS: usize = tetris::TICK_MS as usize;
+/// 开始界面无操作多少秒后进入演示
+const DEMO_IDLE_SECONDS: usize = 10;
 
 fn get_random_u32(rng: &mut Rng) -> u32 {
     let mut buf = [0; 4];
@@ -17,187 +32,313 @@ fn get_random_u32(rng: &mut Rng) -> u32 {
     u32::from_le_bytes(buf)
 }
 
-fn run_game(system_table: &SystemTable<Boot>) -> Result<(), ()>
-{
-    let input_handle = system_table.boot_services().get_handle_for_protocol::<Input>().unwrap();
-    let mut input = system_table.boot_services().open_protocol_exclusive::<Input>(input_handle).unwrap();
-    let gop_handle = system_table.boot_services().get_handle_for_protocol::<GraphicsOutput>().unwrap();
-    let mut gop = system_table.boot_services().open_protocol_exclusive::<GraphicsOutput>(gop_handle).unwrap();
-    let rng_handle = system_table.boot_services().get_handle_for_protocol::<Rng>().unwrap();
-    let mut rng = system_table.boot_services().open_protocol_exclusive::<Rng>(rng_handle).unwrap();
+/// 打开本程序所在的启动卷。与 `BootServices::get_image_file_system` 相同，但是返回 SimpleFileSystem 协议本身
+fn open_boot_volume<'a>(boot_services: &'a BootServices, image_handle: Handle) -> Option<ScopedProtocol<'a, SimpleFileSystem>> {
+    let loaded_image = boot_services.open_protocol_exclusive::<LoadedImage>(image_handle).ok()?;
+    let device_path = boot_services.open_protocol_exclusive::<DevicePath>(loaded_image.device()).ok()?;
+    let device_handle = boot_services.locate_device_path::<SimpleFileSystem>(&mut &*device_path).ok()?;
+    boot_services.open_protocol_exclusive::<SimpleFileSystem>(device_handle).ok()
+}
 
-    let seed = get_random_u32(&mut rng);
-    let board = ExtendedBoard::new(10, 22, new_default_piece_data(), 0);
-    let types = board.piece_data.len();
-    let mut game = Game::new(Config::default(), board, PreviewGenerator::new(SevenBagGenerator::new(seed, types), 5));
-    game.add_next_piece().unwrap();
+/// 游戏用到的 UEFI 协议
+struct Devices<'a> {
+    input: ScopedProtocol<'a, Input>,
+    gop: ScopedProtocol<'a, GraphicsOutput>,
+    rng: ScopedProtocol<'a, Rng>,
+    /// 启动卷，用于保存录像。从网络等没有文件系统的设备启动时为 None
+    fs: Option<ScopedProtocol<'a, SimpleFileSystem>>,
+}
+
+impl<'a> Devices<'a> {
+    fn open(boot_services: &'a BootServices, image_handle: Handle) -> Self {
+        let input_handle = boot_services.get_handle_for_protocol::<Input>().unwrap();
+        let gop_handle = boot_services.get_handle_for_protocol::<GraphicsOutput>().unwrap();
+        let rng_handle = boot_services.get_handle_for_protocol::<Rng>().unwrap();
+        Self {
+            input: boot_services.open_protocol_exclusive::<Input>(input_handle).unwrap(),
+            gop: boot_services.open_protocol_exclusive::<GraphicsOutput>(gop_handle).unwrap(),
+            rng: boot_services.open_protocol_exclusive::<Rng>(rng_handle).unwrap(),
+            fs: open_boot_volume(boot_services, image_handle),
+        }
+    }
+
+    fn new_buffer(&self) -> Buffer {
+        let (width, height) = self.gop.current_mode_info().resolution();
+        Buffer::new(width, height)
+    }
+}
 
-    let (width, height) = gop.current_mode_info().resolution();
-    let mut buffer = Buffer::new(width, height);
+/// 标题菜单的选项
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TitleItem {
+    Play,
+    /// finesse 训练模式
+    Finesse,
+    /// 练习模式
+    Practice,
+    /// 继续存档的游戏
+    Resume,
+    /// 录像列表
+    Replays,
+    Settings,
+    Keys,
+    Demo,
+    /// 退出到固件或者 shell
+    Exit,
+}
 
-    let mut first_piece_dropped = false;
+/// 标题菜单的选项，顺序与菜单项一致
+const TITLE_ITEMS: [TitleItem; 9] = [
+    TitleItem::Play,
+    TitleItem::Finesse,
+    TitleItem::Practice,
+    TitleItem::Resume,
+    TitleItem::Replays,
+    TitleItem::Settings,
+    TitleItem::Keys,
+    TitleItem::Demo,
+    TitleItem::Exit,
+];
 
-    let mut counter = 0;
-    loop {
-        if let Ok(key) = input.read_key() {
-            match key {
-                Some(key) => {
-                    match key {
-                        Key::Printable(c) => {
-                            match unsafe { char::from_u32_unchecked(u16::from(c) as u32) } {
-                                'r' => game.on_event(Event::Forfeit)?,
-                                'z' => game.on_event(Event::Hold)?,
-                                'x' => game.on_event(Event::RotateLeft)?,
-                                'c' => game.on_event(Event::RotateRight)?,
-                                's' => game.on_event(Event::Rotate180)?,
-                                ' ' => game.on_event(Event::HardDrop)?,
-                                _ => {}
-                            }
-                        }
-                        Key::Special(key) => {
-                            match key {
-                                ScanCode::DOWN => game.on_event(Event::SoftDropFast)?,
-                                ScanCode::RIGHT => {
-                                    game.on_event(Event::MoveRightBegin)?;
-                                    game.on_event(Event::MoveRightEnd)?;
-                                }
-                                ScanCode::LEFT => {
-                                    game.on_event(Event::MoveLeftBegin)?;
-                                    game.on_event(Event::MoveLeftEnd)?;
-                                }
-                                _ => {}
-                            }
-                        }
-                    }
-                }
-                None => {
-                    system_table.boot_services().stall(1_000);
-                    counter += 1;
-                    if counter >= 15 {
-                        game.on_event(Event::Tick)?;
-                        render(&mut game, &mut buffer);
-                        buffer.blit(&mut gop).unwrap();
-                        counter = 0;
-
-                        if game.board.board.get_row_filled_count(game.board.board.height - 1) == 0 { // 全清则退出
-                            if first_piece_dropped {
-                                break;
-                            }
-                        } else { // 最下面一行有块表示第一块已放下
-                            first_piece_dropped = true;
-                        }
-                    }
-                }
-            };
+impl TitleItem {
+    fn label(&self) -> &'static str {
+        match self {
+            TitleItem::Play => "PLAY",
+            TitleItem::Finesse => "FINESSE TRAINING",
+            TitleItem::Practice => "PRACTICE",
+            TitleItem::Resume => "RESUME",
+            TitleItem::Replays => "REPLAYS",
+            TitleItem::Settings => "SETTINGS",
+            TitleItem::Keys => "KEYS",
+            TitleItem::Demo => "DEMO",
+            TitleItem::Exit => "EXIT",
         }
     }
-    Ok(())
 }
 
-#[entry]
-fn main(_handle: Handle, mut system_table: SystemTable<Boot>) -> Status {
-    uefi_services::init(&mut system_table).unwrap();
+/// 标题菜单的菜单项，没有存档时不能继续游戏
+fn title_items(has_saved_game: bool) -> Vec<MenuItem> {
+    TITLE_ITEMS
+        .iter()
+        .map(|item| {
+            let menu_item = MenuItem::new(item.label());
+            if *item == TitleItem::Resume && !has_saved_game {
+                menu_item.disabled()
+            } else {
+                menu_item
+            }
+        })
+        .collect()
+}
+
+/// 读取一个菜单操作，没有按键或者按键不用于菜单时返回 None
+fn read_menu_input(devices: &mut Devices) -> Option<MenuInput> {
+    let key = devices.input.read_key().ok()??;
+    frontend::key_code(key).and_then(MenuInput::from_key)
+}
+
+/// 等待一个菜单操作
+fn wait_menu_input(system_table: &SystemTable<Boot>, devices: &mut Devices) -> MenuInput {
     loop {
-        match run_game(&system_table) {
-            Ok(_) => break,
-            Err(_) => {}
+        if let Some(input) = read_menu_input(devices) {
+            return input;
         }
+        system_table.boot_services().stall(1_000);
     }
-    Status::SUCCESS
 }
 
-pub const COLOR_TABLE: &[BltPixel] = &[
-    BltPixel::new(15, 155, 215), // 青 I
-    BltPixel::new(33, 65, 198), // 蓝 J
-    BltPixel::new(227, 91, 2), // 橙 L
-    BltPixel::new(227, 159, 2), // 黄 O
-    BltPixel::new(89, 177, 1), // 绿 S
-    BltPixel::new(175, 41, 138), // 紫 T
-    BltPixel::new(215, 15, 55), // 红 Z
-];
+/// 等待一个可以识别的按键
+fn wait_key_code(system_table: &SystemTable<Boot>, devices: &mut Devices) -> KeyCode {
+    loop {
+        if let Some(key) = devices.input.read_key().ok().flatten().and_then(frontend::key_code) {
+            return key;
+        }
+        system_table.boot_services().stall(1_000);
+    }
+}
 
-fn render<G>(game: &mut Game<PreviewGenerator<usize, G>>, buffer: &mut Buffer)
-    where
-        G: Iterator<Item=usize>
-{
-    let cell_size = buffer.height / (game.board.board.height + 4);
-    let start_x = buffer.width / 2 - game.board.board.width * cell_size / 2;
-    let start_y = buffer.height / 2 - game.board.board.height * cell_size / 2;
-    for y_index in 0..game.board.board.height {
-        for x_index in 0..game.board.board.width {
-            let x = start_x + x_index * cell_size;
-            let y = start_y + y_index * cell_size;
-            if let Some(cell) = game.board.board.get_cell(x_index, y_index) {
-                if let Some(typ) = cell {
-                    buffer.fill_rect(x, y, cell_size, cell_size, COLOR_TABLE[*typ]);
-                } else {
-                    buffer.fill_rect(x, y, cell_size, cell_size, BltPixel::new(0, 0, 0));
-                }
+/// 显示标题菜单，等待选择
+///
+/// returns: Option<TitleItem> 选中的项，长时间无操作时返回 None，进入演示
+fn title_screen(system_table: &SystemTable<Boot>, devices: &mut Devices, menu: &mut Menu) -> Option<TitleItem> {
+    let mut buffer = devices.new_buffer();
+    let has_saved_game = devices.fs.as_mut().is_some_and(|fs| saves::has_saved_game(fs));
+    menu.set_items(title_items(has_saved_game));
+    let idle_ms = DEMO_IDLE_SECONDS * 1000;
+    let mut elapsed_ms = 0;
+    while elapsed_ms < idle_ms {
+        if let Some(input) = read_menu_input(devices) {
+            elapsed_ms = 0;
+            if menu.navigate(input) == Some(MenuInput::Select) {
+                return Some(TITLE_ITEMS[menu.selected]);
             }
         }
+        if elapsed_ms % TICK_MS == 0 {
+            render_title(&mut buffer, 1.0 - elapsed_ms as f32 / idle_ms as f32, menu);
+            buffer.blit(&mut devices.gop).unwrap();
+        }
+        system_table.boot_services().stall(1_000);
+        elapsed_ms += 1;
     }
-    if let Some(current_piece) = &game.board.current_piece {
-        if let Some(piece_data) = game.board.piece_data.get(current_piece.typ) {
-            // 半透明颜色
-            let x_index = current_piece.position.0;
-            let y_index = current_piece.position.1 + game.board.test_fast_drop();
-            let color = COLOR_TABLE[current_piece.typ];
-            let color = BltPixel::new(color.red / 2, color.green / 2, color.blue / 2);
-            for (x_offset, y_offset) in &piece_data.orientation[current_piece.orientation] {
-                let x = (start_x as isize + (x_index + *x_offset as isize) * cell_size as isize) as usize;
-                let y = (start_y as isize + (y_index + *y_offset as isize) * cell_size as isize) as usize;
-                buffer.fill_rect(x, y, cell_size, cell_size, color);
-            }
+    None
+}
 
-            // 正常颜色
-            let x_index = current_piece.position.0;
-            let y_index = current_piece.position.1;
-            let color = COLOR_TABLE[current_piece.typ];
-            for (x_offset, y_offset) in &piece_data.orientation[current_piece.orientation] {
-                let x = (start_x as isize + (x_index + *x_offset as isize) * cell_size as isize) as usize;
-                let y = (start_y as isize + (y_index + *y_offset as isize) * cell_size as isize) as usize;
-                buffer.fill_rect(x, y, cell_size, cell_size, color);
-            }
+/// 设置菜单的菜单项：每个设置一项，最后是返回
+fn settings_items(config: &Config) -> Vec<MenuItem> {
+    let mut items: Vec<MenuItem> = Setting::ALL.iter().map(|setting| MenuItem::with_value(setting.name(), setting.value(config))).collect();
+    items.push(MenuItem::new("BACK"));
+    items
+}
+
+/// 设置菜单：`Left` `Right` 修改选中的设置，修改在下一局开始时生效
+fn settings_menu(system_table: &SystemTable<Boot>, devices: &mut Devices, config: &mut Config) {
+    let mut menu = Menu::new("SETTINGS", settings_items(config));
+    let mut buffer = devices.new_buffer();
+    loop {
+        render_menu(&mut buffer, &menu);
+        buffer.blit(&mut devices.gop).unwrap();
+        let setting = Setting::ALL.get(menu.selected).copied();
+        match (menu.navigate(wait_menu_input(system_table, devices)), setting) {
+            (Some(MenuInput::Left), Some(setting)) => setting.adjust(config, -1),
+            (Some(MenuInput::Right), Some(setting)) => setting.adjust(config, 1),
+            (Some(MenuInput::Back), _) | (Some(MenuInput::Select), None) => return,
+            _ => {}
         }
+        menu.set_items(settings_items(config));
     }
+}
 
-    buffer.fill_rect(start_x + 11 * cell_size, start_y + 2 * cell_size, 4 * cell_size, 15 * cell_size, BltPixel::new(0, 0, 0));
+/// 按键菜单的菜单项：每个操作一项，然后是恢复默认和返回
+fn key_items(bindings: &KeyBindings) -> Vec<MenuItem> {
+    let mut items: Vec<MenuItem> = Control::ALL.iter().map(|control| MenuItem::with_value(control.name(), bindings.key(*control).name())).collect();
+    items.push(MenuItem::new("RESET DEFAULTS"));
+    items.push(MenuItem::new("BACK"));
+    items
+}
 
-    let mut y_index = 2;
-    for typ in game.rng.preview() {
-        if let Some(piece_data) = game.board.piece_data.get(*typ) {
-            let x_index = game.board.board.width as isize + 1 + (4 - piece_data.initial_width as isize) / 2;
-            let color = COLOR_TABLE[*typ];
-            for (x_offset, y_offset) in &piece_data.orientation[0] {
-                let x = (start_x as isize + (x_index + *x_offset as isize) * cell_size as isize) as usize;
-                let y = (start_y as isize + (y_index + *y_offset as isize) * cell_size as isize) as usize;
-                buffer.fill_rect(x, y, cell_size, cell_size, color);
-            }
-            y_index += 3;
+/// 按键菜单：选中一个操作后按下新的按键，`Esc` 取消。新按键原来绑定的操作会换成这个操作原来的按键
+fn keys_menu(system_table: &SystemTable<Boot>, devices: &mut Devices, bindings: &mut KeyBindings) {
+    let mut menu = Menu::new("KEYS", key_items(bindings));
+    let mut buffer = devices.new_buffer();
+    loop {
+        render_menu(&mut buffer, &menu);
+        buffer.blit(&mut devices.gop).unwrap();
+        match menu.navigate(wait_menu_input(system_table, devices)) {
+            Some(MenuInput::Select) => match Control::ALL.get(menu.selected) {
+                Some(control) => {
+                    menu.items[menu.selected].value = "PRESS A KEY".into();
+                    render_menu(&mut buffer, &menu);
+                    buffer.blit(&mut devices.gop).unwrap();
+                    // bind 拒绝 Esc，正好用来取消
+                    bindings.bind(*control, wait_key_code(system_table, devices));
+                }
+                None if menu.selected == Control::ALL.len() => *bindings = KeyBindings::default(),
+                None => return,
+            },
+            Some(MenuInput::Back) => return,
+            _ => {}
         }
+        menu.set_items(key_items(bindings));
     }
+}
 
-    buffer.fill_rect(start_x - 5 * cell_size, start_y + 2 * cell_size, 4 * cell_size, 2 * cell_size, BltPixel::new(0, 0, 0));
+/// 用黑色填满屏幕，退出前调用
+fn clear_screen(devices: &mut Devices) {
+    let buffer = devices.new_buffer();
+    let _ = buffer.blit(&mut devices.gop);
+}
 
-    if let Some(typ) = game.hold {
-        if let Some(piece_data) = game.board.piece_data.get(typ) {
-            let x_index = -5;
-            let y_index = 2;
-            let color = COLOR_TABLE[typ];
-            for (x_offset, y_offset) in &piece_data.orientation[0] {
-                let x = (start_x as isize + (x_index + *x_offset as isize) * cell_size as isize) as usize;
-                let y = (start_y as isize + (y_index + *y_offset as isize) * cell_size as isize) as usize;
-                buffer.fill_rect(x, y, cell_size, cell_size, color);
+/// 机器人演示，按任意键或者游戏结束后返回
+fn run_demo(system_table: &SystemTable<Boot>, devices: &mut Devices) {
+    let seed = get_random_u32(&mut devices.rng);
+    let board = ExtendedBoard::new(10, 22, new_default_piece_data(), 0);
+    let types = board.piece_data.len();
+    let mut game = Game::new(Config::default(), board, PreviewGenerator::new(SevenBagGenerator::new(seed, types), 5));
+    if game.add_next_piece().is_err() {
+        return;
+    }
+    let mut bot = Bot::new(BotConfig {
+        pps: 2.5,
+        tick_rate: 1000.0 / TICK_MS as f32,
+        depth: 2,
+        ..BotConfig::default()
+    });
+    let mut buffer = devices.new_buffer();
+    let mut counter = 0;
+    loop {
+        if let Ok(Some(_)) = devices.input.read_key() {
+            return;
+        }
+        system_table.boot_services().stall(1_000);
+        counter += 1;
+        if counter >= TICK_MS {
+            if bot.step(&mut game).is_err() {
+                return;
             }
+            render_game(&mut game, &mut buffer, None, Some(seed));
+            buffer.blit(&mut devices.gop).unwrap();
+            counter = 0;
         }
     }
+}
+
+/// 开始新的一局游戏
+fn run_game(system_table: &SystemTable<Boot>, devices: &mut Devices, config: Config, bindings: KeyBindings) {
+    let seed = get_random_u32(&mut devices.rng);
+    let replay = Replay::new(seed, DEFAULT_PIECE_SET, 10, 22, 0, 5, config);
+    let game = replay.new_game(true).unwrap();
+    continue_game(system_table, devices, replay, game, bindings);
+}
 
-    buffer.fill_rect(start_x - 1, start_y + 2 * cell_size, 1, (game.board.board.height - 2) * cell_size + 1, BltPixel::new(255, 255, 255));
-    buffer.fill_rect(start_x - 1, start_y + 2 * cell_size, game.board.board.width * cell_size + 2, 1, BltPixel::new(255, 255, 255));
-    buffer.fill_rect(start_x - 1, start_y + game.board.board.height * cell_size + 1, game.board.board.width * cell_size + 2, 1, BltPixel::new(255, 255, 255));
-    buffer.fill_rect(start_x + game.board.board.width * cell_size + 1, start_y + 2 * cell_size, 1, (game.board.board.height - 2) * cell_size + 1, BltPixel::new(255, 255, 255));
+/// 继续存档的游戏。存档读取后立即删除，同一个存档只能继续一次
+fn resume_game(system_table: &SystemTable<Boot>, devices: &mut Devices, bindings: KeyBindings) {
+    if let Some((game, replay)) = BootVolume::new(system_table, devices.fs.as_deref_mut()).take_saved_game() {
+        continue_game(system_table, devices, replay, game, bindings);
+    }
+}
+
+/// 进行游戏，结束后回到标题菜单。暂停时把游戏保存到启动卷的存档，否则结束后把录像保存到启动卷
+fn continue_game(system_table: &SystemTable<Boot>, devices: &mut Devices, mut replay: Replay, mut game: ReplayGame, bindings: KeyBindings) {
+    let mut input = KeyboardInput::new(&mut devices.input, bindings);
+    let mut clock = StallClock::new(system_table.boot_services());
+    let mut screen = Screen::new(&mut devices.gop, replay.seed);
+    let mut storage = BootVolume::new(system_table, devices.fs.as_deref_mut());
+    tetris::run_game(&mut game, &mut replay, &mut input, &mut clock, &mut screen, &mut storage);
+}
+
+#[entry]
+fn main(handle: Handle, mut system_table: SystemTable<Boot>) -> Status {
+    uefi_services::init(&mut system_table).unwrap();
+    let mut devices = Devices::open(system_table.boot_services(), handle);
+    // 只能检测按键按下，没有 DAS
+    let mut config = Config {
+        finesse_das: false,
+        ..Config::default()
+    };
+    let mut bindings = KeyBindings::default();
+    let mut menu = Menu::new("TETRIS", title_items(false));
+    loop {
+        match title_screen(&system_table, &mut devices, &mut menu) {
+            Some(TitleItem::Play) => run_game(&system_table, &mut devices, config, bindings),
+            // 训练和练习模式会影响游戏结果，只能在开局前选择，保证录像可以复现
+            Some(TitleItem::Finesse) => run_game(&system_table, &mut devices, Config { finesse_training: true, ..config }, bindings),
+            Some(TitleItem::Practice) => run_game(&system_table, &mut devices, Config { practice: true, ..config }, bindings),
+            Some(TitleItem::Resume) => resume_game(&system_table, &mut devices, bindings),
+            Some(TitleItem::Replays) => replays::replay_browser(&system_table, &mut devices),
+            Some(TitleItem::Settings) => settings_menu(&system_table, &mut devices, &mut config),
+            Some(TitleItem::Keys) => keys_menu(&system_table, &mut devices, &mut bindings),
+            Some(TitleItem::Demo) | None => run_demo(&system_table, &mut devices),
+            Some(TitleItem::Exit) => break,
+        }
+    }
+    clear_screen(&mut devices);
+    // 文本输出需要可变的系统表，先关闭借用了它的协议
+    drop(devices);
+    let _ = system_table.stdout().clear();
+    Status::SUCCESS
 }
 
+/// GOP 的缓冲区，绘制完成后一次性复制到屏幕
 pub struct Buffer {
     pub width: usize,
     pub height: usize,
@@ -217,16 +358,6 @@ impl Buffer {
         self.pixels.get_mut(y * self.width + x)
     }
 
-    fn fill_rect(&mut self, x: usize, y: usize, cx: usize, cy: usize, color: BltPixel) {
-        for y1 in y..(y + cy).min(self.height) {
-            for x1 in x..(x + cx).min(self.width) {
-                if let Some(pixel) = self.pixel(x1, y1) {
-                    *pixel = color;
-                }
-            }
-        }
-    }
-
     fn blit(&self, gop: &mut GraphicsOutput) -> uefi::Result {
         gop.blt(BltOp::BufferToVideo {
             buffer: &self.pixels,
@@ -236,3 +367,24 @@ impl Buffer {
         })
     }
 }
+
+impl Framebuffer for Buffer {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn fill_rect(&mut self, x: usize, y: usize, cx: usize, cy: usize, color: Rgb) {
+        let color = BltPixel::new(color.r, color.g, color.b);
+        for y1 in y..(y + cy).min(self.height) {
+            for x1 in x..(x + cx).min(self.width) {
+                if let Some(pixel) = self.pixel(x1, y1) {
+                    *pixel = color;
+                }
+            }
+        }
+    }
+}
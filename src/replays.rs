@@ -0,0 +1,166 @@
+//! 录像文件：保存在启动卷的 `\replays\` 目录下，文件名为结束时的固件时间
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use tetris::{render_game, render_menu, Menu, MenuInput, MenuItem, Replay};
+use uefi::prelude::*;
+use uefi::proto::media::file::{Directory, File, FileAttribute, FileMode, RegularFile};
+use uefi::proto::media::fs::SimpleFileSystem;
+use uefi::{cstr16, CString16};
+
+use crate::{wait_menu_input, Devices, TICK_MS};
+
+/// 录像列表一页显示的录像数，480 行的屏幕能放下
+const PAGE_SIZE: usize = 12;
+/// 同一个时间的录像最多保存多少个
+const MAX_NAME_INDEX: usize = 100;
+
+/// 打开录像目录，不存在时创建
+fn open_replay_dir(fs: &mut SimpleFileSystem) -> uefi::Result<Directory> {
+    let mut root = fs.open_volume()?;
+    let handle = root.open(cstr16!("replays"), FileMode::CreateReadWrite, FileAttribute::DIRECTORY)?;
+    handle.into_directory().ok_or_else(|| Status::UNSUPPORTED.into())
+}
+
+fn to_cstring16(name: &str) -> uefi::Result<CString16> {
+    CString16::try_from(name).map_err(|_| Status::INVALID_PARAMETER.into())
+}
+
+/// 保存录像，文件名形如 `20230801-213000.rpl`。同名的录像已经存在时（例如同一秒内结束）加上序号，形如 `20230801-213000-1.rpl`
+pub fn save_replay(system_table: &SystemTable<Boot>, fs: &mut SimpleFileSystem, replay: &Replay) -> uefi::Result {
+    let time = system_table.runtime_services().get_time()?;
+    let stem = format!("{:04}{:02}{:02}-{:02}{:02}{:02}", time.year(), time.month(), time.day(), time.hour(), time.minute(), time.second());
+    let mut dir = open_replay_dir(fs)?;
+    for index in 0..MAX_NAME_INDEX {
+        let name = if index == 0 { format!("{}.rpl", stem) } else { format!("{}-{}.rpl", stem, index) };
+        let name = to_cstring16(&name)?;
+        // CreateReadWrite 打开已有文件时不会截断，覆盖会留下旧文件末尾的数据，所以只写入新文件
+        match dir.open(&name, FileMode::Read, FileAttribute::empty()) {
+            Ok(_) => continue,
+            Err(err) if err.status() == Status::NOT_FOUND => {}
+            Err(err) => return Err(err),
+        }
+        let mut file = dir.open(&name, FileMode::CreateReadWrite, FileAttribute::empty())?
+            .into_regular_file()
+            .ok_or_else(|| uefi::Error::from(Status::UNSUPPORTED))?;
+        file.write(&replay.encode()).map_err(|err| uefi::Error::from(err.status()))?;
+        return file.flush();
+    }
+    Err(Status::ACCESS_DENIED.into())
+}
+
+/// 列出所有录像文件，最新的在前
+pub fn list_replays(fs: &mut SimpleFileSystem) -> uefi::Result<Vec<String>> {
+    let mut dir = open_replay_dir(fs)?;
+    let mut names = Vec::new();
+    while let Some(info) = dir.read_entry_boxed()? {
+        if !info.attribute().contains(FileAttribute::DIRECTORY) {
+            names.push(info.file_name().to_string());
+        }
+    }
+    names.sort_unstable_by(|a, b| b.cmp(a));
+    Ok(names)
+}
+
+/// 读取录像文件。文件格式错误时返回 Ok(None)
+pub fn load_replay(fs: &mut SimpleFileSystem, name: &str) -> uefi::Result<Option<Replay>> {
+    let mut dir = open_replay_dir(fs)?;
+    let mut file: RegularFile = dir.open(&to_cstring16(name)?, FileMode::Read, FileAttribute::empty())?
+        .into_regular_file()
+        .ok_or_else(|| uefi::Error::from(Status::UNSUPPORTED))?;
+    Ok(Replay::decode(&read_file(&mut file)?))
+}
+
+/// 读取整个文件
+pub fn read_file(file: &mut RegularFile) -> uefi::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let len = file.read(&mut chunk).map_err(|err| uefi::Error::from(err.status()))?;
+        if len == 0 {
+            break;
+        }
+        data.extend_from_slice(&chunk[..len]);
+    }
+    Ok(data)
+}
+
+/// 录像列表的一页，第一项为 first。没有录像时显示一个不可用的项
+fn replay_menu(names: &[String], first: usize) -> Menu {
+    let page_count = names.len().div_ceil(PAGE_SIZE).max(1);
+    let title = format!("REPLAYS {}/{}", first / PAGE_SIZE + 1, page_count);
+    let mut items: Vec<MenuItem> = names.iter().skip(first).take(PAGE_SIZE).map(|name| MenuItem::new(name)).collect();
+    if items.is_empty() {
+        items.push(MenuItem::new("NO REPLAYS").disabled());
+    }
+    Menu::new(&title, items)
+}
+
+/// 录像列表：`Up` `Down` 选择，到一页的两端时翻页，`Enter` 观看，`Esc` 返回
+pub fn replay_browser(system_table: &SystemTable<Boot>, devices: &mut Devices) {
+    let names = match devices.fs.as_mut().map(|fs| list_replays(fs)) {
+        Some(Ok(names)) => names,
+        _ => Vec::new(),
+    };
+    let mut buffer = devices.new_buffer();
+    let mut selected = 0;
+    // 读取失败的录像，在列表中标出
+    let mut failed: Vec<usize> = Vec::new();
+    loop {
+        let first = selected / PAGE_SIZE * PAGE_SIZE;
+        let mut menu = replay_menu(&names, first);
+        menu.selected = selected - first;
+        for index in &failed {
+            if let Some(item) = index.checked_sub(first).and_then(|index| menu.items.get_mut(index)) {
+                item.value = "FAILED".into();
+            }
+        }
+        render_menu(&mut buffer, &menu);
+        buffer.blit(&mut devices.gop).unwrap();
+
+        match wait_menu_input(system_table, devices) {
+            MenuInput::Up => selected = selected.saturating_sub(1),
+            MenuInput::Down => selected = (selected + 1).min(names.len().saturating_sub(1)),
+            MenuInput::Back => return,
+            MenuInput::Select => {
+                if let Some(name) = names.get(selected) {
+                    let replay = devices.fs.as_mut().and_then(|fs| load_replay(fs, name).ok().flatten());
+                    match replay {
+                        Some(replay) => watch_replay(system_table, devices, &replay),
+                        None => failed.push(selected),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 观看录像，按任意键返回。录像结束后停在最后一帧
+pub fn watch_replay(system_table: &SystemTable<Boot>, devices: &mut Devices, replay: &Replay) {
+    let mut game = match replay.new_game(false) {
+        Some(game) => game,
+        None => return,
+    };
+    game.count_finesse = true;
+    let mut player = replay.player();
+    let mut buffer = devices.new_buffer();
+    let mut playing = true;
+    let mut counter = 0;
+    loop {
+        if let Ok(Some(_)) = devices.input.read_key() {
+            return;
+        }
+        system_table.boot_services().stall(1_000);
+        counter += 1;
+        if counter >= TICK_MS {
+            if playing {
+                playing = player.step(&mut game);
+                render_game(&mut game, &mut buffer, None, Some(replay.seed));
+                buffer.blit(&mut devices.gop).unwrap();
+            }
+            counter = 0;
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//! 存档文件：暂停的游戏保存在启动卷根目录的 `tetris.sav`，继续游戏时读取并删除
+
+use tetris::{decode_save, encode_save, Replay, ReplayGame};
+use uefi::prelude::*;
+use uefi::proto::media::file::{File, FileAttribute, FileMode};
+use uefi::proto::media::fs::SimpleFileSystem;
+use uefi::{cstr16, CStr16};
+
+use crate::replays::read_file;
+
+const SAVE_FILE_NAME: &CStr16 = cstr16!("tetris.sav");
+
+/// 是否有存档
+pub fn has_saved_game(fs: &mut SimpleFileSystem) -> bool {
+    fs.open_volume()
+        .and_then(|mut root| root.open(SAVE_FILE_NAME, FileMode::Read, FileAttribute::empty()))
+        .is_ok()
+}
+
+/// 保存暂停的游戏和它的录像开局参数，覆盖已有的存档
+pub fn save_game(fs: &mut SimpleFileSystem, game: &ReplayGame, replay: &Replay) -> uefi::Result {
+    // 新数据可能比旧文件短，先删除旧文件
+    delete_saved_game(fs)?;
+    let mut root = fs.open_volume()?;
+    let mut file = root.open(SAVE_FILE_NAME, FileMode::CreateReadWrite, FileAttribute::empty())?
+        .into_regular_file()
+        .ok_or_else(|| uefi::Error::from(Status::UNSUPPORTED))?;
+    file.write(&encode_save(&(game, replay))).map_err(|err| uefi::Error::from(err.status()))?;
+    file.flush()
+}
+
+/// 读取存档。没有存档或者格式错误时返回 Ok(None)
+pub fn load_saved_game(fs: &mut SimpleFileSystem) -> uefi::Result<Option<(ReplayGame, Replay)>> {
+    let mut root = fs.open_volume()?;
+    let handle = match root.open(SAVE_FILE_NAME, FileMode::Read, FileAttribute::empty()) {
+        Ok(handle) => handle,
+        Err(err) if err.status() == Status::NOT_FOUND => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    let mut file = handle.into_regular_file().ok_or_else(|| uefi::Error::from(Status::UNSUPPORTED))?;
+    Ok(decode_save(&read_file(&mut file)?))
+}
+
+/// 删除存档，没有存档时什么也不做
+pub fn delete_saved_game(fs: &mut SimpleFileSystem) -> uefi::Result {
+    let mut root = fs.open_volume()?;
+    match root.open(SAVE_FILE_NAME, FileMode::ReadWrite, FileAttribute::empty()) {
+        Ok(handle) => handle.delete(),
+        Err(err) if err.status() == Status::NOT_FOUND => Ok(()),
+        Err(err) => Err(err),
+    }
+}
@@ -0,0 +1,108 @@
+//! ## 版面分析
+//!
+//! 机器人、提示和统计面板常用的版面特征。只遍历一遍位掩码，适合对每个候选落点调用。
+
+use crate::{Board, MAX_BOARD_WIDTH};
+
+/// 版面分析结果
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoardAnalysis {
+    /// 版面宽度，column_heights 和 well_depths 中只有前 width 个有效
+    pub width: usize,
+    /// 每列的高度：最上面的已填充小块到版面底部的行数，空列为 0
+    pub column_heights: [usize; MAX_BOARD_WIDTH],
+    /// 最大列高度
+    pub max_height: usize,
+    /// 列高度之和
+    pub aggregate_height: usize,
+    /// 空洞数：上方有已填充小块的空格子数
+    pub holes: usize,
+    /// 被覆盖的小块数：每列最低的空洞上方的已填充小块数之和
+    pub covered_cells: usize,
+    /// 凹凸度：相邻两列高度差的绝对值之和
+    pub bumpiness: usize,
+    /// 每列的井深：左右两列（墙视为与版面等高）中较低的一列比本列高出的行数
+    pub well_depths: [usize; MAX_BOARD_WIDTH],
+    /// 井深之和
+    pub well_depth_sum: usize,
+    /// 最深的井：(列, 深度)
+    pub deepest_well: (usize, usize),
+    /// 行变换数：每行中相邻格子填充状态变化的次数之和，两侧墙视为已填充
+    pub row_transitions: usize,
+    /// 列变换数：每列中相邻格子填充状态变化的次数之和，底部视为已填充
+    pub column_transitions: usize,
+}
+
+impl Board {
+    /// 计算版面特征
+    pub fn analyze(&self) -> BoardAnalysis {
+        let mut result = BoardAnalysis {
+            width: self.width,
+            column_heights: [0; MAX_BOARD_WIDTH],
+            max_height: 0,
+            aggregate_height: 0,
+            holes: 0,
+            covered_cells: 0,
+            bumpiness: 0,
+            well_depths: [0; MAX_BOARD_WIDTH],
+            well_depth_sum: 0,
+            deepest_well: (0, 0),
+            row_transitions: 0,
+            column_transitions: 0,
+        };
+        let full_row_mask = self.full_row_mask();
+        let transition_mask = (1u128 << (self.width + 1)) - 1;
+        // 上方已经出现过已填充小块的列
+        let mut covered = 0;
+        // 每列到目前为止的已填充小块数，以及最低的空洞上方的已填充小块数
+        let mut filled_count = [0; MAX_BOARD_WIDTH];
+        let mut covered_count = [0; MAX_BOARD_WIDTH];
+        for y in 0..self.height {
+            let row = self.get_row_mask(y);
+
+            let mut columns = row & !covered;
+            while columns != 0 {
+                result.column_heights[columns.trailing_zeros() as usize] = self.height - y;
+                columns &= columns - 1;
+            }
+
+            let holes = covered & !row & full_row_mask;
+            result.holes += holes.count_ones() as usize;
+            let mut columns = holes;
+            while columns != 0 {
+                let x = columns.trailing_zeros() as usize;
+                covered_count[x] = filled_count[x];
+                columns &= columns - 1;
+            }
+            let mut columns = row;
+            while columns != 0 {
+                filled_count[columns.trailing_zeros() as usize] += 1;
+                columns &= columns - 1;
+            }
+            covered |= row;
+
+            let walled = ((row as u128) << 1) | 1 | (1 << (self.width + 1));
+            result.row_transitions += ((walled ^ (walled >> 1)) & transition_mask).count_ones() as usize;
+            let below = if y + 1 < self.height { self.get_row_mask(y + 1) } else { full_row_mask };
+            result.column_transitions += (row ^ below).count_ones() as usize;
+        }
+        result.covered_cells = covered_count.iter().sum();
+
+        let column_heights = result.column_heights;
+        let heights = &column_heights[..self.width];
+        result.max_height = heights.iter().copied().max().unwrap_or(0);
+        result.aggregate_height = heights.iter().sum();
+        result.bumpiness = heights.windows(2).map(|pair| pair[0].abs_diff(pair[1])).sum();
+        for x in 0..self.width {
+            let left = if x == 0 { self.height } else { heights[x - 1] };
+            let right = if x + 1 == self.width { self.height } else { heights[x + 1] };
+            let depth = left.min(right).saturating_sub(heights[x]);
+            result.well_depths[x] = depth;
+            result.well_depth_sum += depth;
+            if depth > result.deepest_well.1 {
+                result.deepest_well = (x, depth);
+            }
+        }
+        result
+    }
+}
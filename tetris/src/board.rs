@@ -18,32 +18,161 @@ use alloc::string::String;
 use alloc::vec::Vec;
 use alloc::vec;
 
+/// 版面的最大宽度：每行用一个 u64 位掩码表示
+pub const MAX_BOARD_WIDTH: usize = 64;
+/// 版面的最大高度，也用于拒绝录像和存档中异常的版面大小
+pub const MAX_BOARD_HEIGHT: usize = 1024;
+
 /// 四连方块的基本数据
+#[derive(Clone)]
 pub struct PieceData {
-    /// 名称（未使用）
+    /// 名称：fumen 和 TBP 用它对应标准方块
     pub name: String,
     /// 初始状态宽度：用于确定四连方块进场的位置
     pub initial_width: usize,
     /// 不同朝向的形态数据。第一层是方块的当前方向，第二层是每个小块的偏移。
-    pub orientation: Vec<Vec<(usize, usize)>>,
+    /// 只能在创建时设置，保证与 masks 一致，读取用 `orientation()`
+    pub(crate) orientation: Vec<Vec<(usize, usize)>>,
     /// 踢墙表。第一层是方块的当前方向，第二层是将要如何旋转，第三层是多个测试位置。
     pub test_table: Vec<Vec<Vec<(isize, isize)>>>,
+    /// 进场时的朝向
+    pub spawn_orientation: usize,
+    /// 进场位置偏移：在默认进场位置（水平居中，第 start_y 行）的基础上再平移 (dx, dy)
+    pub spawn_offset: (isize, isize),
+    /// 颜色（皮肤）编号：锁定后写入版面，前端据此查颜色表
+    pub color_id: usize,
+    /// 是否判定 T-spin：旋转后锁定并且满足三角判定时算作 T-spin
+    pub t_spin: bool,
+    /// 每个朝向预先计算好的位掩码，与 orientation 一一对应，由 `PieceData::new` 计算
+    pub(crate) masks: Vec<PieceMask>,
+}
+
+impl PieceData {
+    /// 创建四连方块数据，并预先计算每个朝向的位掩码
+    ///
+    /// 进场朝向为 0，进场偏移为 (0, 0)，颜色编号为 0，不判定 T-spin，创建后可以直接修改这几个字段
+    pub fn new(name: String, initial_width: usize, orientation: Vec<Vec<(usize, usize)>>, test_table: Vec<Vec<Vec<(isize, isize)>>>) -> Self {
+        let masks = orientation.iter().map(|cells| PieceMask::new(cells)).collect();
+        Self {
+            name,
+            initial_width,
+            orientation,
+            test_table,
+            spawn_orientation: 0,
+            spawn_offset: (0, 0),
+            color_id: 0,
+            t_spin: false,
+            masks,
+        }
+    }
+
+    /// 不同朝向的形态数据
+    pub fn orientation(&self) -> &[Vec<(usize, usize)>] {
+        &self.orientation
+    }
+
+    /// 两个状态是否占据相同的格子（例如 S 的 0 和 2 方向，O 的所有方向）
+    pub fn same_cells(&self, (a, a_orientation): ((isize, isize), usize), (b, b_orientation): ((isize, isize), usize)) -> bool {
+        if let (Some(a_mask), Some(b_mask)) = (self.masks.get(a_orientation), self.masks.get(b_orientation)) {
+            a.0 + a_mask.left as isize == b.0 + b_mask.left as isize
+                && a.1 + a_mask.top as isize == b.1 + b_mask.top as isize
+                && a_mask.rows == b_mask.rows
+        } else {
+            false
+        }
+    }
+}
+
+/// 四连方块某个朝向的位掩码形态
+#[derive(Clone)]
+pub struct PieceMask {
+    /// 最左边小块的 x 偏移
+    pub left: usize,
+    /// 最右边小块的 x 偏移
+    pub right: usize,
+    /// 最上边小块的 y 偏移
+    pub top: usize,
+    /// 从 top 行开始，每行一个位掩码。第 0 位对应 left 列
+    pub rows: Vec<u64>,
+}
+
+impl PieceMask {
+    pub fn new(cells: &[(usize, usize)]) -> Self {
+        let left = cells.iter().map(|(x, _)| *x).min().unwrap_or(0);
+        let right = cells.iter().map(|(x, _)| *x).max().unwrap_or(0);
+        let top = cells.iter().map(|(_, y)| *y).min().unwrap_or(0);
+        let bottom = cells.iter().map(|(_, y)| *y + 1).max().unwrap_or(0);
+        assert!(right - left < MAX_BOARD_WIDTH);
+        let mut rows = vec![0; bottom - top];
+        for (x, y) in cells {
+            rows[*y - top] |= 1 << (*x - left);
+        }
+        Self {
+            left,
+            right,
+            top,
+            rows,
+        }
+    }
 }
 
 /// 版面
+#[derive(Clone)]
 pub struct Board {
     /// 版面宽度
     pub width: usize,
     /// 版面高度
     pub height: usize,
-    /// 版面：22 行 10 列。None 表示没有填充。usize 表示一个颜色
-    pub board: Vec<Option<usize>>,
+    /// 每个小块的颜色：22 行 10 列。None 表示没有填充。usize 表示一个颜色
+    colors: Vec<Option<usize>>,
+    /// 每行一个位掩码，第 x 位表示第 x 列是否已填充。用于碰撞检测和满行检测
+    rows: Vec<u64>,
+    /// 最近一次标记的满行的行号，从上到下
+    marked_rows: Vec<usize>,
+    /// 最近一次标记的满行的内容，每 width 个为一行
+    marked_cells: Vec<Option<usize>>,
+}
+
+/// 被标记或已消除的满行。数据保存在版面内部可复用的缓冲区中，一次最多消除 4 行时不会分配内存
+pub struct ClearedRows<'a> {
+    width: usize,
+    /// 行号（消除前的行号），从上到下
+    pub indices: &'a [usize],
+    /// 每一行的内容，每 width 个为一行
+    pub cells: &'a [Option<usize>],
+}
+
+impl<'a> ClearedRows<'a> {
+    /// 行数
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// 第 i 行的内容
+    pub fn row(&self, i: usize) -> &'a [Option<usize>] {
+        &self.cells[i * self.width..(i + 1) * self.width]
+    }
+
+    /// 遍历每一行的行号和内容
+    pub fn iter(&self) -> impl Iterator<Item=(usize, &'a [Option<usize>])> + '_ {
+        self.indices.iter().enumerate().map(|(i, y)| (*y, self.row(i)))
+    }
 }
 
 impl Board {
+    /// 创建空版面。宽度为 1 到 MAX_BOARD_WIDTH，高度不能超过 MAX_BOARD_HEIGHT
     pub fn new(width: usize, height: usize) -> Self {
+        assert!((1..=MAX_BOARD_WIDTH).contains(&width));
+        assert!(height <= MAX_BOARD_HEIGHT);
         Self {
-            board: vec![None; width * height],
+            colors: vec![None; width * height],
+            rows: vec![0; height],
+            marked_rows: Vec::with_capacity(4),
+            marked_cells: Vec::with_capacity(4 * width),
             width,
             height,
         }
@@ -51,7 +180,8 @@ impl Board {
 
     /// 清空版面
     pub fn clear(&mut self) {
-        self.board.fill(None);
+        self.colors.fill(None);
+        self.rows.fill(0);
     }
 
     /// 获取一个小块的状态，第一个 Option 是当前 (x,y) 是否存在，第二个 Option 是当前小块是否已填充
@@ -59,92 +189,134 @@ impl Board {
         if x >= self.width || y >= self.height {
             return None;
         }
-        self.board.get(y * self.width + x)
+        self.colors.get(y * self.width + x)
     }
 
-    /// 获取一个小块的状态，第一个 Option 是当前 (x,y) 是否存在，第二个 Option 是当前小块是否已填充
-    pub fn get_cell_mut(&mut self, x: usize, y: usize) -> Option<&mut Option<usize>> {
+    /// 设置一个小块的状态。None 表示清空，Some 表示用指定颜色填充
+    ///
+    /// returns: bool (x,y) 是否在版面内
+    pub fn set_cell(&mut self, x: usize, y: usize, cell: Option<usize>) -> bool {
         if x >= self.width || y >= self.height {
-            return None;
+            return false;
+        }
+        self.colors[y * self.width + x] = cell;
+        if cell.is_some() {
+            self.rows[y] |= 1 << x;
+        } else {
+            self.rows[y] &= !(1 << x);
+        }
+        true
+    }
+
+    /// 获取一行的位掩码，第 x 位表示第 x 列是否已填充。超出版面的行视为空行
+    pub fn get_row_mask(&self, y: usize) -> u64 {
+        self.rows.get(y).copied().unwrap_or(0)
+    }
+
+    /// 填满一行时的位掩码
+    pub fn full_row_mask(&self) -> u64 {
+        if self.width == MAX_BOARD_WIDTH {
+            u64::MAX
+        } else {
+            (1 << self.width) - 1
         }
-        self.board.get_mut(y * self.width + x)
     }
 
     /// 获取一行中已填充的小块的数量
     pub fn get_row_filled_count(&self, y: usize) -> usize {
-        let mut count = 0;
-        for x in 0..self.width {
-            if let Some(cell) = self.get_cell(x, y) {
-                if cell.is_some() {
-                    count += 1;
-                }
-            }
-        }
-        count
+        self.get_row_mask(y).count_ones() as usize
+    }
+
+    /// 一行是否已经填满
+    pub fn is_row_full(&self, y: usize) -> bool {
+        y < self.height && self.rows[y] == self.full_row_mask()
     }
 
     /// 复制行
     pub fn copy_row(&mut self, src_y: usize, dst_y: usize) {
-        for x in 0..self.width {
-            self.board[dst_y * self.width + x] = self.board[src_y * self.width + x];
-        }
+        self.colors.copy_within(src_y * self.width..(src_y + 1) * self.width, dst_y * self.width);
+        self.rows[dst_y] = self.rows[src_y];
     }
 
     /// 清除行，不下落
     pub fn clear_row(&mut self, y: usize) {
-        for x in 0..self.width {
-            if let Some(cell) = self.get_cell_mut(x, y) {
-                *cell = None
+        if y < self.height {
+            self.colors[y * self.width..(y + 1) * self.width].fill(None);
+            self.rows[y] = 0;
+        }
+    }
+
+    /// 获取非空行的数量
+    pub fn get_non_empty_row_count(&self) -> usize {
+        self.rows.iter().filter(|row| **row != 0).count()
+    }
+
+    /// 标记所有填满的行并保存它们的内容，但不消除。
+    /// 前端可以在标记和 collapse_marked_rows 之间播放消行动画
+    pub fn mark_filled_rows(&mut self) -> ClearedRows<'_> {
+        let full_row_mask = self.full_row_mask();
+        self.marked_rows.clear();
+        self.marked_cells.clear();
+        for y in 0..self.height {
+            if self.rows[y] == full_row_mask {
+                self.marked_rows.push(y);
+                self.marked_cells.extend_from_slice(&self.colors[y * self.width..(y + 1) * self.width]);
             }
         }
+        self.marked_rows()
     }
 
-    /// 消除所有填满的行，并下落
+    /// 最近一次标记的行。消除之后仍然可以获取，直到下一次标记
+    pub fn marked_rows(&self) -> ClearedRows<'_> {
+        ClearedRows {
+            width: self.width,
+            indices: &self.marked_rows,
+            cells: &self.marked_cells,
+        }
+    }
+
+    /// 消除已标记的行（无论现在是否仍然填满），上方的行下落
     ///
-    /// returns: 返回清除的行数和剩余的非空行数
-    pub fn clear_filled_rows(&mut self) -> (usize, usize) {
-        let mut cleared_row_count = 0;
-        let mut non_empty_row_count = 0;
-        let mut dst_y = self.height - 1;
+    /// returns: 被消除的行
+    pub fn collapse_marked_rows(&mut self) -> ClearedRows<'_> {
+        let mut dst_y = self.height;
+        let mut marked_index = self.marked_rows.len();
         for src_y in (0..self.height).rev() {
-            let filled_count = self.get_row_filled_count(src_y);
-            if filled_count != 0 {
-                if filled_count != self.width {
-                    self.copy_row(src_y, dst_y);
-                    dst_y -= 1;
-                    non_empty_row_count += 1;
-                } else {
-                    cleared_row_count += 1;
-                }
-            }
-        }
-        loop {
-            self.clear_row(dst_y);
-            if dst_y == 0 {
-                break;
+            if marked_index > 0 && self.marked_rows[marked_index - 1] == src_y {
+                marked_index -= 1;
+                continue;
             }
             dst_y -= 1;
+            if dst_y != src_y {
+                self.copy_row(src_y, dst_y);
+            }
+        }
+        for y in 0..dst_y {
+            self.clear_row(y);
         }
-        (cleared_row_count, non_empty_row_count)
+        self.marked_rows()
+    }
+
+    /// 消除所有填满的行，并下落
+    ///
+    /// returns: 被消除的行
+    pub fn clear_filled_rows(&mut self) -> ClearedRows<'_> {
+        self.mark_filled_rows();
+        self.collapse_marked_rows()
     }
 
     /// 尝试四连方块能否以指定朝向放入指定位置
     pub fn test_piece(&self, piece_data: &PieceData, (x, y): (isize, isize), orientation: usize) -> bool {
-        if let Some(cells) = piece_data.orientation.get(orientation) {
-            for (cell_dx, cell_dy) in cells {
-                match (cell_dx.checked_add_signed(x), cell_dy.checked_add_signed(y)) {
-                    (Some(x2), Some(y2)) => {
-                        if let Some(cell) = self.get_cell(x2, y2) {
-                            if let Some(_) = cell {
-                                return false;
-                            }
-                        } else {
-                            return false;
-                        }
-                    }
-                    _ => {
-                        return false;
-                    }
+        if let Some(mask) = piece_data.masks.get(orientation) {
+            let left = x + mask.left as isize;
+            let top = y + mask.top as isize;
+            if left < 0 || top < 0 || x + mask.right as isize >= self.width as isize || top + mask.rows.len() as isize > self.height as isize {
+                return false;
+            }
+            let (left, top) = (left as usize, top as usize);
+            for (i, row) in mask.rows.iter().enumerate() {
+                if self.rows[top + i] & (row << left) != 0 {
+                    return false;
                 }
             }
             true
@@ -180,17 +352,21 @@ impl Board {
         None
     }
 
+    /// 测试四连方块从指定位置可以直接下落多少格
+    pub fn test_drop_distance(&self, piece_data: &PieceData, (x, y): (isize, isize), orientation: usize) -> isize {
+        let mut dy = 0;
+        while self.test_piece(piece_data, (x, y + dy + 1), orientation) {
+            dy += 1;
+        }
+        dy
+    }
+
     /// 锁定（将四连方块填充到版面中）
     pub fn lock_piece(&mut self, piece_data: &PieceData, (x, y): (isize, isize), orientation: usize, color_id: usize) {
         if let Some(cells) = piece_data.orientation.get(orientation) {
             for (cell_dx, cell_dy) in cells {
-                match (cell_dx.checked_add_signed(x), cell_dy.checked_add_signed(y)) {
-                    (Some(x2), Some(y2)) => {
-                        if let Some(cell) = self.get_cell_mut(x2, y2) {
-                            *cell = Some(color_id);
-                        }
-                    }
-                    _ => {}
+                if let (Some(x2), Some(y2)) = (cell_dx.checked_add_signed(x), cell_dy.checked_add_signed(y)) {
+                    self.set_cell(x2, y2, Some(color_id));
                 }
             }
         }
@@ -198,6 +374,7 @@ impl Board {
 }
 
 /// 四连方块当前状态
+#[derive(Clone, Debug)]
 pub struct Piece {
     /// 类型（对应 ExtendedBoard 的 Vec<PieceData> 的下标）
     pub typ: usize,
@@ -208,6 +385,7 @@ pub struct Piece {
 }
 
 /// 带当前四连方块的版面
+#[derive(Clone)]
 pub struct ExtendedBoard {
     /// 四连方块数据表
     pub piece_data: Vec<PieceData>,
@@ -234,13 +412,15 @@ impl ExtendedBoard {
         self.current_piece = None;
     }
 
+    /// 在指定位置以进场朝向添加一个指定类型的方块
     pub fn add_piece(&mut self, typ: usize, position: (isize, isize)) -> bool {
         if let Some(piece_data) = self.piece_data.get(typ) {
-            if self.board.test_piece(piece_data, position, 0) {
+            let orientation = piece_data.spawn_orientation;
+            if self.board.test_piece(piece_data, position, orientation) {
                 self.current_piece = Some(Piece {
                     typ,
                     position,
-                    orientation: 0,
+                    orientation,
                 });
                 return true;
             }
@@ -250,14 +430,21 @@ impl ExtendedBoard {
 
     /// 在默认位置添加一个指定类型的方块
     pub fn add_piece_default_position(&mut self, typ: usize) -> bool {
-        if let Some(piece_data) = self.piece_data.get(typ) {
-            let start_x = (self.board.width - piece_data.initial_width) / 2;
-            self.add_piece(typ, (start_x as isize, self.start_y))
+        if let Some(position) = self.spawn_position(typ) {
+            self.add_piece(typ, position)
         } else {
             false
         }
     }
 
+    /// 指定类型的方块的默认进场位置：水平居中，第 start_y 行，再加上 spawn_offset
+    pub fn spawn_position(&self, typ: usize) -> Option<(isize, isize)> {
+        self.piece_data.get(typ).map(|piece_data| {
+            let start_x = (self.board.width as isize - piece_data.initial_width as isize) / 2;
+            (start_x + piece_data.spawn_offset.0, self.start_y + piece_data.spawn_offset.1)
+        })
+    }
+
     /// 平移+旋转
     ///
     /// # Arguments
@@ -289,20 +476,17 @@ impl ExtendedBoard {
 
     /// 测试快速降落需要移动多少格
     pub fn test_fast_drop(&self) -> isize {
-        let mut dy = 0;
         if let Some(current_piece) = &self.current_piece {
             if let Some(piece_data) = self.piece_data.get(current_piece.typ) {
-                while let Some(_) = self.board.test_move_piece(piece_data, current_piece.position, current_piece.orientation, (0, dy + 1), 0) {
-                    dy += 1;
-                }
+                return self.board.test_drop_distance(piece_data, current_piece.position, current_piece.orientation);
             }
         }
-        dy
+        0
     }
 
     /// 是否已经降落，没有方块时视
     pub fn is_land(&self) -> bool {
-        if let Some(_) = &self.current_piece {
+        if self.current_piece.is_some() {
             self.test_fast_drop() == 0
         } else {
             false
@@ -313,7 +497,7 @@ impl ExtendedBoard {
     pub fn lock_piece(&mut self) {
         if let Some(current_piece) = &self.current_piece {
             if let Some(piece_data) = self.piece_data.get(current_piece.typ) {
-                self.board.lock_piece(piece_data, current_piece.position, current_piece.orientation, current_piece.typ);
+                self.board.lock_piece(piece_data, current_piece.position, current_piece.orientation, piece_data.color_id);
             }
         }
         self.current_piece = None;
@@ -0,0 +1,344 @@
+//! ## 机器人
+//!
+//! 用加权的版面评估给落点打分，在当前方块、Hold 和预览序列上做束搜索。
+//! 机器人不直接修改版面，而是通过 Game::on_event 发送真实的事件来放置方块。
+
+use crate::{reachable_placements, simulate_path, Board, Event, ExtendedBoard, Game, Input, Placement, PreviewGenerator};
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+/// 版面评估权重。正数为奖励，负数为惩罚
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Weights {
+    /// 列高度之和
+    pub aggregate_height: f32,
+    /// 最大列高度
+    pub max_height: f32,
+    /// 空洞数
+    pub holes: f32,
+    /// 空洞上方的已填充小块数
+    pub covered_cells: f32,
+    /// 凹凸度
+    pub bumpiness: f32,
+    /// 除最深的井以外的井深之和
+    pub well_depth: f32,
+    /// 最深的井的深度（最多计 4 行），用于保留消四的井
+    pub deepest_well: f32,
+    /// 行变换数
+    pub row_transitions: f32,
+    /// 列变换数
+    pub column_transitions: f32,
+    /// 可以放入 T 做 T-spin Double 的槽数
+    pub t_slots: f32,
+    /// 普通消行奖励，下标为消除行数
+    pub clears: [f32; 5],
+    /// T-spin 消行奖励，下标为消除行数
+    pub t_spin_clears: [f32; 4],
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            aggregate_height: -0.2,
+            max_height: -0.3,
+            holes: -4.0,
+            covered_cells: -0.5,
+            bumpiness: -0.3,
+            well_depth: -0.5,
+            deepest_well: 0.4,
+            row_transitions: -0.3,
+            column_transitions: -0.4,
+            t_slots: 1.5,
+            clears: [0.0, -1.0, -0.5, 0.5, 6.0],
+            t_spin_clears: [0.0, 2.0, 7.0, 10.0],
+        }
+    }
+}
+
+impl Weights {
+    /// 评估版面
+    pub fn evaluate(&self, board: &Board) -> f32 {
+        let analysis = board.analyze();
+        let deepest_well = analysis.deepest_well.1.min(4);
+        self.aggregate_height * analysis.aggregate_height as f32
+            + self.max_height * analysis.max_height as f32
+            + self.holes * analysis.holes as f32
+            + self.covered_cells * analysis.covered_cells as f32
+            + self.bumpiness * analysis.bumpiness as f32
+            + self.well_depth * (analysis.well_depth_sum - analysis.deepest_well.1) as f32
+            + self.deepest_well * deepest_well as f32
+            + self.row_transitions * analysis.row_transitions as f32
+            + self.column_transitions * analysis.column_transitions as f32
+            + self.t_slots * count_t_slots(board) as f32
+    }
+
+    /// 消行奖励
+    pub fn clear_reward(&self, lines: usize, t_spin: bool) -> f32 {
+        if t_spin {
+            self.t_spin_clears.get(lines).copied().unwrap_or(0.0)
+        } else {
+            self.clears.get(lines).copied().unwrap_or(0.0)
+        }
+    }
+}
+
+/// 统计可以做 T-spin Double 的槽
+///
+/// 槽的形状如下（`#` 已填充，`.` 为空，`?` 两侧恰好有一个已填充作为屋檐），并且最下面一行只差槽中的一格：
+///
+/// ```text
+/// ? . ?
+/// . . .
+/// # . #
+/// ```
+pub fn count_t_slots(board: &Board) -> usize {
+    let mut count = 0;
+    for y in 0..board.height.saturating_sub(2) {
+        let (top, middle, bottom) = (board.get_row_mask(y), board.get_row_mask(y + 1), board.get_row_mask(y + 2));
+        if bottom.count_ones() as usize + 1 != board.width {
+            continue;
+        }
+        for x in 0..board.width.saturating_sub(2) {
+            let slot = 0b111 << x;
+            let center = 0b010 << x;
+            if bottom & slot == slot & !center && middle & slot == 0 && top & center == 0 && (top & slot).count_ones() == 1 {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+/// 三角判定：T 方块的中心四角中至少有三个被占据（墙和地板也算）
+///
+/// * `position`: T 方块的位置，中心为 (x + 1, y + 1)
+pub(crate) fn is_t_spin(board: &Board, position: (isize, isize)) -> bool {
+    let (x, y) = (position.0 + 1, position.1 + 1);
+    let corners = [(-1, -1), (1, -1), (-1, 1), (1, 1)].iter()
+        .filter(|(dx, dy)| {
+            let (x, y) = (x + dx, y + dy);
+            x < 0 || y < 0 || !matches!(board.get_cell(x as usize, y as usize), Some(None))
+        })
+        .count();
+    corners >= 3
+}
+
+/// 机器人设置
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BotConfig {
+    /// 每秒放置的方块数，为 0 时不等待
+    pub pps: f32,
+    /// 游戏每秒的 tick 数
+    pub tick_rate: f32,
+    /// 搜索深度：向后看几个方块（包括当前方块），受预览数限制
+    pub depth: usize,
+    /// 每层保留的候选数
+    pub beam_width: usize,
+    /// 是否使用 Hold
+    pub use_hold: bool,
+    /// 是否使用 180 度旋转
+    pub allow_180: bool,
+    /// 评估权重
+    pub weights: Weights,
+}
+
+impl Default for BotConfig {
+    fn default() -> Self {
+        Self {
+            pps: 2.0,
+            tick_rate: 60.0,
+            depth: 3,
+            beam_width: 8,
+            use_hold: true,
+            allow_180: true,
+            weights: Weights::default(),
+        }
+    }
+}
+
+/// 搜索结果
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Decision {
+    /// 是否先 Hold
+    pub hold: bool,
+    /// 落点
+    pub placement: Placement,
+    /// 整个序列的得分
+    pub score: f32,
+}
+
+/// 束搜索中的一个状态
+struct Node {
+    board: Board,
+    hold: Option<usize>,
+    /// 下一个要放置的方块在序列中的下标
+    next: usize,
+    /// 已经获得的消行奖励
+    reward: f32,
+    score: f32,
+    first: Option<(bool, Placement)>,
+}
+
+pub struct Bot {
+    /// 机器人设置
+    pub config: BotConfig,
+    /// 还没有发送的事件
+    events: VecDeque<Event>,
+    /// 可以开始放置下一个方块的 tick
+    next_piece_tick: f32,
+}
+
+impl Bot {
+    pub fn new(config: BotConfig) -> Self {
+        Self {
+            config,
+            events: VecDeque::new(),
+            next_piece_tick: 0.0,
+        }
+    }
+
+    /// 搜索当前方块最好的落点
+    ///
+    /// # Arguments
+    ///
+    /// * `board`: 版面，当前方块为序列的第一个
+    /// * `hold`: 当前 Hold 的方块
+    /// * `hold_available`: 当前方块能否 Hold
+    /// * `queue`: 方块序列：当前方块和预览
+    ///
+    /// returns: Option<Decision> 无处可放时返回 None
+    pub fn think(&self, board: &ExtendedBoard, hold: Option<usize>, hold_available: bool, queue: &[usize]) -> Option<Decision> {
+        let mut nodes = vec![Node {
+            board: board.board.clone(),
+            hold,
+            next: 0,
+            reward: 0.0,
+            score: 0.0,
+            first: None,
+        }];
+        for depth in 0..self.config.depth {
+            let mut children = Vec::new();
+            for node in &nodes {
+                self.expand(board, node, depth == 0 && !hold_available, queue, &mut children);
+            }
+            if children.is_empty() {
+                break;
+            }
+            children.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+            children.truncate(self.config.beam_width.max(1));
+            nodes = children;
+        }
+        let node = nodes.first()?;
+        let (hold, placement) = node.first?;
+        Some(Decision {
+            hold,
+            placement,
+            score: node.score,
+        })
+    }
+
+    /// 放置一个方块，生成所有子状态
+    fn expand(&self, board: &ExtendedBoard, node: &Node, no_hold: bool, queue: &[usize], children: &mut Vec<Node>) {
+        let current = match queue.get(node.next) {
+            Some(current) => *current,
+            None => return,
+        };
+        // (方块类型, 放置后的 Hold, 消耗的方块数, 是否 Hold)
+        let mut options = vec![(current, node.hold, 1, false)];
+        if self.config.use_hold && !no_hold {
+            match node.hold {
+                Some(hold) if hold != current => options.push((hold, Some(current), 1, true)),
+                None => if let Some(next) = queue.get(node.next + 1) {
+                    options.push((*next, Some(current), 2, true));
+                },
+                _ => {}
+            }
+        }
+        for (typ, hold, consumed, used_hold) in options {
+            let piece_data = match board.piece_data.get(typ) {
+                Some(piece_data) => piece_data,
+                None => continue,
+            };
+            // 第一个方块从当前状态出发，其他的从进场状态出发
+            let start = match &board.current_piece {
+                Some(current_piece) if node.first.is_none() && !used_hold => Some((current_piece.position, current_piece.orientation)),
+                _ => board.spawn_position(typ).map(|position| (position, piece_data.spawn_orientation)),
+            };
+            let (position, orientation) = match start {
+                Some(start) => start,
+                None => continue,
+            };
+            for placement in reachable_placements(&node.board, piece_data, position, orientation, self.config.allow_180) {
+                let mut child_board = node.board.clone();
+                child_board.lock_piece(piece_data, placement.position, placement.orientation, piece_data.color_id);
+                let t_spin = placement.spin && piece_data.t_spin && is_t_spin(&node.board, placement.position);
+                let lines = child_board.clear_filled_rows().len();
+                let reward = node.reward + self.config.weights.clear_reward(lines, t_spin);
+                let score = reward + self.config.weights.evaluate(&child_board);
+                children.push(Node {
+                    board: child_board,
+                    hold,
+                    next: node.next + consumed,
+                    reward,
+                    score,
+                    first: node.first.or(Some((used_hold, placement))),
+                });
+            }
+        }
+    }
+
+    /// 每帧调用一次，代替前端发送的 Tick 事件
+    ///
+    /// 新方块出现后，按照 pps 等待到放置时间，再搜索落点并生成事件序列。
+    /// 事件按顺序发送给 Game，遇到 Tick（DAS 需要经过若干帧）则结束这一帧。
+    pub fn step<G>(&mut self, game: &mut Game<PreviewGenerator<usize, G>>) -> Result<(), ()>
+        where
+            G: Iterator<Item=usize>
+    {
+        if self.events.is_empty() && game.current_tick as f32 >= self.next_piece_tick {
+            if let Some(current_typ) = game.board.current_piece.as_ref().map(|piece| piece.typ) {
+                if self.config.pps > 0.0 {
+                    self.next_piece_tick = game.current_tick as f32 + self.config.tick_rate / self.config.pps;
+                }
+                let mut queue = vec![current_typ];
+                queue.extend(game.rng.preview().copied());
+                self.plan(game, &queue);
+            }
+        }
+        while let Some(event) = self.events.pop_front() {
+            game.on_event(event)?;
+            if event == Event::Tick {
+                return Ok(());
+            }
+        }
+        game.on_event(Event::Tick)
+    }
+
+    /// 搜索落点，并生成放置当前方块的事件序列
+    fn plan<G>(&mut self, game: &Game<G>, queue: &[usize]) {
+        self.events.clear();
+        let decision = match self.think(&game.board, game.hold, !game.hold_used, queue) {
+            Some(decision) => decision,
+            None => {
+                self.events.push_back(Event::HardDrop);
+                return;
+            }
+        };
+        let target = (decision.placement.position, decision.placement.orientation);
+        // 在副本上模拟，预览序列之后的方块未知
+        let mut simulation = game.with_rng(queue.get(1..).unwrap_or_default().to_vec().into_iter());
+        if decision.hold {
+            self.events.push_back(Event::Hold);
+            if simulation.on_event(Event::Hold).is_err() {
+                self.events.push_back(Event::HardDrop);
+                return;
+            }
+        }
+        match simulate_path(&mut simulation, target, &Input::moves(self.config.allow_180, true)) {
+            Some(events) => self.events.extend(events),
+            None => self.events.push_back(Event::HardDrop),
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//! ## 二进制编码
+//!
+//! 录像、存档等紧凑格式使用的读写工具。整数使用 LEB128 变长编码，有符号整数先做 zigzag 变换，浮点数按小端序原样保存。
+//!
+//! 实现了 Encode 和 Decode 的类型可以直接用 Writer::write 和 Reader::read 读写；变长的列表和字符串先写入长度。
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// 写入器
+#[derive(Clone, Debug, Default)]
+pub struct Writer {
+    buffer: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 取出写入的数据
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buffer
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.buffer.push(value);
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// 写入变长整数：每个字节低 7 位为数据，最高位表示后面还有字节
+    pub fn write_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buffer.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.buffer.push(value as u8);
+    }
+
+    pub fn write_usize(&mut self, value: usize) {
+        self.write_varint(value as u64);
+    }
+
+    pub fn write_isize(&mut self, value: isize) {
+        let value = value as i64;
+        self.write_varint(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.write_u8(value as u8);
+    }
+
+    pub fn write_f32(&mut self, value: f32) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    /// 写入定长 64 位整数（小端序），适合校验值等高位分布均匀的数据
+    pub fn write_u64(&mut self, value: u64) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    /// 写入实现了 Encode 的值
+    pub fn write<T: Encode + ?Sized>(&mut self, value: &T) {
+        value.write_to(self);
+    }
+}
+
+/// 读取器。数据不足或者格式错误时返回 None
+#[derive(Clone, Debug)]
+pub struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+    /// 数据的格式版本，0 表示当前版本
+    version: u8,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self::with_version(data, 0)
+    }
+
+    /// 读取旧版本的数据，Decode 的实现根据 version() 兼容旧格式
+    pub fn with_version(data: &'a [u8], version: u8) -> Self {
+        Self {
+            data,
+            position: 0,
+            version,
+        }
+    }
+
+    /// 数据的格式版本，0 表示当前版本
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// 是否已经读完
+    pub fn is_empty(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    /// 剩余的字节数
+    pub fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.position)
+    }
+
+    /// 读取实现了 Decode 的值
+    pub fn read<T: Decode>(&mut self) -> Option<T> {
+        T::read_from(self)
+    }
+
+    pub fn read_u8(&mut self) -> Option<u8> {
+        let value = *self.data.get(self.position)?;
+        self.position += 1;
+        Some(value)
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.position..self.position.checked_add(len)?)?;
+        self.position += len;
+        Some(bytes)
+    }
+
+    pub fn read_varint(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    pub fn read_usize(&mut self) -> Option<usize> {
+        usize::try_from(self.read_varint()?).ok()
+    }
+
+    pub fn read_isize(&mut self) -> Option<isize> {
+        let value = self.read_varint()?;
+        isize::try_from((value >> 1) as i64 ^ -((value & 1) as i64)).ok()
+    }
+
+    pub fn read_bool(&mut self) -> Option<bool> {
+        match self.read_u8()? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    pub fn read_f32(&mut self) -> Option<f32> {
+        Some(f32::from_le_bytes(self.read_bytes(4)?.try_into().ok()?))
+    }
+
+    pub fn read_u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.read_bytes(8)?.try_into().ok()?))
+    }
+}
+
+/// 可以写入 Writer 的类型
+pub trait Encode {
+    fn write_to(&self, writer: &mut Writer);
+}
+
+/// 可以从 Reader 读出的类型
+pub trait Decode: Sized {
+    fn read_from(reader: &mut Reader) -> Option<Self>;
+}
+
+macro_rules! impl_codec {
+    ($typ:ty, $write:ident, $read:ident) => {
+        impl Encode for $typ {
+            fn write_to(&self, writer: &mut Writer) {
+                writer.$write(*self);
+            }
+        }
+
+        impl Decode for $typ {
+            fn read_from(reader: &mut Reader) -> Option<Self> {
+                reader.$read()
+            }
+        }
+    };
+}
+
+impl_codec!(u8, write_u8, read_u8);
+impl_codec!(usize, write_usize, read_usize);
+impl_codec!(isize, write_isize, read_isize);
+impl_codec!(bool, write_bool, read_bool);
+impl_codec!(f32, write_f32, read_f32);
+
+impl Encode for u32 {
+    fn write_to(&self, writer: &mut Writer) {
+        writer.write_varint(*self as u64);
+    }
+}
+
+impl Decode for u32 {
+    fn read_from(reader: &mut Reader) -> Option<Self> {
+        u32::try_from(reader.read_varint()?).ok()
+    }
+}
+
+impl Encode for str {
+    fn write_to(&self, writer: &mut Writer) {
+        writer.write_usize(self.len());
+        writer.write_bytes(self.as_bytes());
+    }
+}
+
+impl Encode for String {
+    fn write_to(&self, writer: &mut Writer) {
+        writer.write(self.as_str());
+    }
+}
+
+impl Decode for String {
+    fn read_from(reader: &mut Reader) -> Option<Self> {
+        let len = reader.read_usize()?;
+        String::from_utf8(reader.read_bytes(len)?.to_vec()).ok()
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    fn write_to(&self, writer: &mut Writer) {
+        (**self).write_to(writer);
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn write_to(&self, writer: &mut Writer) {
+        writer.write_bool(self.is_some());
+        if let Some(value) = self {
+            writer.write(value);
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn read_from(reader: &mut Reader) -> Option<Self> {
+        if reader.read_bool()? {
+            Some(Some(reader.read()?))
+        } else {
+            Some(None)
+        }
+    }
+}
+
+impl<T: Encode> Encode for [T] {
+    fn write_to(&self, writer: &mut Writer) {
+        writer.write_usize(self.len());
+        for value in self {
+            writer.write(value);
+        }
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn write_to(&self, writer: &mut Writer) {
+        writer.write(self.as_slice());
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn read_from(reader: &mut Reader) -> Option<Self> {
+        let len = reader.read_usize()?;
+        // 长度来自外部数据，每个元素至少占一个字节，不按长度预先分配
+        if len > reader.remaining() {
+            return None;
+        }
+        let mut values = Vec::new();
+        for _ in 0..len {
+            values.push(reader.read()?);
+        }
+        Some(values)
+    }
+}
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+    fn write_to(&self, writer: &mut Writer) {
+        writer.write(&self.0);
+        writer.write(&self.1);
+    }
+}
+
+impl<A: Decode, B: Decode> Decode for (A, B) {
+    fn read_from(reader: &mut Reader) -> Option<Self> {
+        Some((reader.read()?, reader.read()?))
+    }
+}
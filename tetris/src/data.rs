@@ -3,6 +3,9 @@ use alloc::vec;
 use alloc::vec::Vec;
 use alloc::string::ToString;
 
+/// 垃圾行的颜色编号，排在默认的七种四连方块之后
+pub const GARBAGE_COLOR_ID: usize = 7;
+
 /// 标准四连方块和标准 SRS 踢墙表
 /// https://tetris.wiki/Super_Rotation_System
 pub fn new_default_piece_data() -> Vec<PieceData> {
@@ -86,81 +89,114 @@ pub fn new_default_piece_data() -> Vec<PieceData> {
     ];
     vec![
         PieceData {
-            name: "I".to_string(),
-            initial_width: 4,
-            orientation: vec![
-                vec![(0, 1), (1, 1), (2, 1), (3, 1)], // 0
-                vec![(2, 0), (2, 1), (2, 2), (2, 3)], // R
-                vec![(0, 2), (1, 2), (2, 2), (3, 2)], // 2
-                vec![(1, 0), (1, 1), (1, 2), (1, 3)], // L
-            ],
-            test_table: srs_i.clone(),
+            color_id: 0,
+            ..PieceData::new(
+                "I".to_string(),
+                4,
+                vec![
+                    vec![(0, 1), (1, 1), (2, 1), (3, 1)], // 0
+                    vec![(2, 0), (2, 1), (2, 2), (2, 3)], // R
+                    vec![(0, 2), (1, 2), (2, 2), (3, 2)], // 2
+                    vec![(1, 0), (1, 1), (1, 2), (1, 3)], // L
+                ],
+                srs_i.clone(),
+            )
         },
         PieceData {
-            name: "J".to_string(),
-            initial_width: 3,
-            orientation: vec![
-                vec![(0, 1), (1, 1), (2, 1), (0, 0)],
-                vec![(1, 0), (1, 1), (1, 2), (2, 0)],
-                vec![(0, 1), (1, 1), (2, 1), (2, 2)],
-                vec![(1, 0), (1, 1), (1, 2), (0, 2)],
-            ],
-            test_table: srs_jlstz.clone(),
+            color_id: 1,
+            ..PieceData::new(
+                "J".to_string(),
+                3,
+                vec![
+                    vec![(0, 1), (1, 1), (2, 1), (0, 0)],
+                    vec![(1, 0), (1, 1), (1, 2), (2, 0)],
+                    vec![(0, 1), (1, 1), (2, 1), (2, 2)],
+                    vec![(1, 0), (1, 1), (1, 2), (0, 2)],
+                ],
+                srs_jlstz.clone(),
+            )
         },
         PieceData {
-            name: "L".to_string(),
-            initial_width: 3,
-            orientation: vec![
-                vec![(0, 1), (1, 1), (2, 1), (2, 0)],
-                vec![(1, 0), (1, 1), (1, 2), (2, 2)],
-                vec![(0, 1), (1, 1), (2, 1), (0, 2)],
-                vec![(1, 0), (1, 1), (1, 2), (0, 0)],
-            ],
-            test_table: srs_jlstz.clone(),
+            color_id: 2,
+            ..PieceData::new(
+                "L".to_string(),
+                3,
+                vec![
+                    vec![(0, 1), (1, 1), (2, 1), (2, 0)],
+                    vec![(1, 0), (1, 1), (1, 2), (2, 2)],
+                    vec![(0, 1), (1, 1), (2, 1), (0, 2)],
+                    vec![(1, 0), (1, 1), (1, 2), (0, 0)],
+                ],
+                srs_jlstz.clone(),
+            )
         },
         PieceData {
-            name: "O".to_string(),
-            initial_width: 2,
-            orientation: vec![
-                vec![(0, 0), (0, 1), (1, 1), (1, 0)],
-                vec![(0, 0), (0, 1), (1, 1), (1, 0)],
-                vec![(0, 0), (0, 1), (1, 1), (1, 0)],
-                vec![(0, 0), (0, 1), (1, 1), (1, 0)],
-            ],
-            test_table: srs_o.clone(),
+            color_id: 3,
+            ..PieceData::new(
+                "O".to_string(),
+                2,
+                vec![
+                    vec![(0, 0), (0, 1), (1, 1), (1, 0)],
+                    vec![(0, 0), (0, 1), (1, 1), (1, 0)],
+                    vec![(0, 0), (0, 1), (1, 1), (1, 0)],
+                    vec![(0, 0), (0, 1), (1, 1), (1, 0)],
+                ],
+                srs_o.clone(),
+            )
         },
         PieceData {
-            name: "S".to_string(),
-            initial_width: 3,
-            orientation: vec![
-                vec![(0, 1), (1, 1), (1, 0), (2, 0)],
-                vec![(1, 0), (1, 1), (2, 1), (2, 2)],
-                vec![(0, 2), (1, 2), (1, 1), (2, 1)],
-                vec![(0, 0), (0, 1), (1, 1), (1, 2)],
-            ],
-            test_table: srs_jlstz.clone(),
+            color_id: 4,
+            ..PieceData::new(
+                "S".to_string(),
+                3,
+                vec![
+                    vec![(0, 1), (1, 1), (1, 0), (2, 0)],
+                    vec![(1, 0), (1, 1), (2, 1), (2, 2)],
+                    vec![(0, 2), (1, 2), (1, 1), (2, 1)],
+                    vec![(0, 0), (0, 1), (1, 1), (1, 2)],
+                ],
+                srs_jlstz.clone(),
+            )
         },
         PieceData {
-            name: "T".to_string(),
-            initial_width: 3,
-            orientation: vec![
-                vec![(1, 1), (0, 1), (1, 0), (2, 1)],
-                vec![(1, 1), (1, 2), (1, 0), (2, 1)],
-                vec![(1, 1), (0, 1), (1, 2), (2, 1)],
-                vec![(1, 1), (0, 1), (1, 0), (1, 2)],
-            ],
-            test_table: srs_jlstz.clone(),
+            color_id: 5,
+            t_spin: true,
+            ..PieceData::new(
+                "T".to_string(),
+                3,
+                vec![
+                    vec![(1, 1), (0, 1), (1, 0), (2, 1)],
+                    vec![(1, 1), (1, 2), (1, 0), (2, 1)],
+                    vec![(1, 1), (0, 1), (1, 2), (2, 1)],
+                    vec![(1, 1), (0, 1), (1, 0), (1, 2)],
+                ],
+                srs_jlstz.clone(),
+            )
         },
         PieceData {
-            name: "Z".to_string(),
-            initial_width: 3,
-            orientation: vec![
-                vec![(0, 0), (1, 0), (1, 1), (2, 1)],
-                vec![(2, 0), (2, 1), (1, 1), (1, 2)],
-                vec![(0, 1), (1, 1), (1, 2), (2, 2)],
-                vec![(1, 0), (1, 1), (0, 1), (0, 2)],
-            ],
-            test_table: srs_jlstz.clone(),
+            color_id: 6,
+            ..PieceData::new(
+                "Z".to_string(),
+                3,
+                vec![
+                    vec![(0, 0), (1, 0), (1, 1), (2, 1)],
+                    vec![(2, 0), (2, 1), (1, 1), (1, 2)],
+                    vec![(0, 1), (1, 1), (1, 2), (2, 2)],
+                    vec![(1, 0), (1, 1), (0, 1), (0, 2)],
+                ],
+                srs_jlstz.clone(),
+            )
         },
     ]
-}
\ No newline at end of file
+}
+
+/// 默认方块组（标准四连方块和 SRS 踢墙表）的编号
+pub const DEFAULT_PIECE_SET: usize = 0;
+
+/// 按编号创建方块组，录像中只记录这个编号。未知的编号返回 None
+pub fn new_piece_data(piece_set: usize) -> Option<Vec<PieceData>> {
+    match piece_set {
+        DEFAULT_PIECE_SET => Some(new_default_piece_data()),
+        _ => None,
+    }
+}
@@ -0,0 +1,295 @@
+//! ## 前端
+//!
+//! UEFI、终端和无界面前端共用的游戏循环。平台相关的部分分成四个 trait：
+//!
+//! * InputSource：把按键映射为 Action
+//! * Clock：毫秒时钟和等待
+//! * Renderer：每个 tick 之后显示画面
+//! * Storage：保存录像和暂停的游戏
+//!
+//! 同时提供无界面的实现（ScriptedInput、ManualClock、NullRenderer、MemoryStorage），用于测试和批量运行。
+
+use crate::{decode_save, encode_save, Bot, BotConfig, Event, Game, Piece, PreviewGenerator, Replay, ReplayGame};
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// 每个 tick 的毫秒数
+pub const TICK_MS: u64 = 15;
+
+/// 前端的输入动作
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// 发送给 Game 的事件
+    Event(Event),
+    /// 切换落点提示
+    ToggleHint,
+    /// 暂停并保存，稍后继续
+    Suspend,
+    /// 退出，与游戏结束一样保存录像
+    Quit,
+}
+
+/// 输入源
+pub trait InputSource {
+    /// 取出下一个输入动作，没有时返回 None，不阻塞
+    fn poll(&mut self, now_ms: u64) -> Option<Action>;
+}
+
+/// 时钟
+pub trait Clock {
+    /// 当前时间（毫秒）
+    fn now_ms(&mut self) -> u64;
+    /// 等待一段时间（毫秒）
+    fn sleep_ms(&mut self, ms: u64);
+}
+
+/// 画面输出
+pub trait Renderer {
+    /// 显示当前画面
+    ///
+    /// * `hint`: 提示的落点，没有开启提示时为 None
+    fn render(&mut self, game: &mut ReplayGame, hint: Option<&Piece>);
+}
+
+/// 持久化存储。保存失败不影响游戏，由调用者决定是否提示
+pub trait Storage {
+    /// 保存一局结束的录像
+    fn save_replay(&mut self, replay: &Replay) -> Result<(), ()>;
+    /// 保存暂停的游戏和它的录像开局参数，覆盖已有的存档
+    fn save_game(&mut self, game: &ReplayGame, replay: &Replay) -> Result<(), ()>;
+    /// 读取并删除存档，同一个存档只能继续一次。读取失败时保留存档
+    fn take_saved_game(&mut self) -> Option<(ReplayGame, Replay)>;
+    /// 是否有存档
+    fn has_saved_game(&mut self) -> bool;
+}
+
+/// 一局结束的原因
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameEnd {
+    /// 全部消除
+    AllClear,
+    /// 无法放置新方块，或者放弃
+    GameOver,
+    /// 暂停，游戏已经保存到存档
+    Suspended,
+    /// 退出
+    Quit,
+}
+
+/// 落点提示：机器人认为当前方块（或者 Hold 之后的方块）最好的落点
+pub struct Hint {
+    pub enabled: bool,
+    bot: Bot,
+    /// 计算提示时的状态：(已锁定方块数, 当前方块类型, Hold)，状态变化时重新计算
+    key: Option<(usize, usize, Option<usize>)>,
+    piece: Option<Piece>,
+}
+
+impl Default for Hint {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hint {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            bot: Bot::new(BotConfig {
+                depth: 2,
+                ..BotConfig::default()
+            }),
+            key: None,
+            piece: None,
+        }
+    }
+
+    pub fn update<G>(&mut self, game: &mut Game<PreviewGenerator<usize, G>>)
+        where
+            G: Iterator<Item=usize>
+    {
+        let current_typ = match &game.board.current_piece {
+            Some(current_piece) => current_piece.typ,
+            None => return,
+        };
+        let key = Some((game.pieces, current_typ, game.hold));
+        if self.key == key {
+            return;
+        }
+        self.key = key;
+        let mut queue = vec![current_typ];
+        queue.extend(game.rng.preview().copied());
+        self.piece = self.bot.think(&game.board, game.hold, !game.hold_used, &queue).and_then(|decision| {
+            let typ = if decision.hold {
+                game.hold.or(queue.get(1).copied())?
+            } else {
+                current_typ
+            };
+            Some(Piece {
+                typ,
+                position: decision.placement.position,
+                orientation: decision.placement.orientation,
+            })
+        });
+    }
+
+    /// 开启时返回提示的落点
+    pub fn piece(&self) -> Option<&Piece> {
+        if self.enabled {
+            self.piece.as_ref()
+        } else {
+            None
+        }
+    }
+}
+
+/// 进行一局游戏，直到全部消除、游戏结束、暂停或者退出
+///
+/// 每 TICK_MS 毫秒发送一次 Tick 并渲染，其余时间处理输入。暂停时把游戏保存到存档，否则结束后把录像保存到存储
+pub fn run_game<I, C, R, S>(game: &mut ReplayGame, replay: &mut Replay, input: &mut I, clock: &mut C, renderer: &mut R, storage: &mut S) -> GameEnd
+    where
+        I: InputSource,
+        C: Clock,
+        R: Renderer,
+        S: Storage
+{
+    let end = play(game, input, clock, renderer);
+    if end == GameEnd::Suspended {
+        let _ = storage.save_game(game, replay);
+    } else {
+        replay.finish(game);
+        let _ = storage.save_replay(replay);
+    }
+    end
+}
+
+fn play<I, C, R>(game: &mut ReplayGame, input: &mut I, clock: &mut C, renderer: &mut R) -> GameEnd
+    where
+        I: InputSource,
+        C: Clock,
+        R: Renderer
+{
+    // HUD 显示 finesse 错误数
+    game.count_finesse = true;
+    let mut hint = Hint::new();
+    let mut next_tick = clock.now_ms() + TICK_MS;
+    loop {
+        let now = clock.now_ms();
+        while let Some(action) = input.poll(now) {
+            match action {
+                Action::Event(event) => {
+                    if game.on_event(event).is_err() {
+                        return GameEnd::GameOver;
+                    }
+                }
+                Action::ToggleHint => hint.enabled = !hint.enabled,
+                Action::Suspend => return GameEnd::Suspended,
+                Action::Quit => return GameEnd::Quit,
+            }
+        }
+        if now < next_tick {
+            clock.sleep_ms(1);
+            continue;
+        }
+        next_tick += TICK_MS;
+        if game.on_event(Event::Tick).is_err() {
+            return GameEnd::GameOver;
+        }
+        if hint.enabled {
+            hint.update(game);
+        }
+        renderer.render(game, hint.piece());
+        // 已经锁定过方块并且最下面一行为空表示全清。练习模式撤销到开局时方块数为 0，不算全清
+        if game.pieces > 0 && game.board.board.get_row_filled_count(game.board.board.height - 1) == 0 {
+            return GameEnd::AllClear;
+        }
+    }
+}
+
+/// 按时间顺序给出预先写好的输入，用于测试和无界面运行
+#[derive(Clone, Debug, Default)]
+pub struct ScriptedInput {
+    /// (时间, 动作)，按时间排序
+    actions: VecDeque<(u64, Action)>,
+}
+
+impl ScriptedInput {
+    pub fn new(mut actions: Vec<(u64, Action)>) -> Self {
+        actions.sort_by_key(|(ms, _)| *ms);
+        Self {
+            actions: actions.into(),
+        }
+    }
+
+    /// 剩余的输入数
+    pub fn remaining(&self) -> usize {
+        self.actions.len()
+    }
+}
+
+impl InputSource for ScriptedInput {
+    fn poll(&mut self, now_ms: u64) -> Option<Action> {
+        if self.actions.front().is_some_and(|(ms, _)| *ms <= now_ms) {
+            self.actions.pop_front().map(|(_, action)| action)
+        } else {
+            None
+        }
+    }
+}
+
+/// 虚拟时钟：等待时立即把时间往前推
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ManualClock {
+    pub now_ms: u64,
+}
+
+impl Clock for ManualClock {
+    fn now_ms(&mut self) -> u64 {
+        self.now_ms
+    }
+
+    fn sleep_ms(&mut self, ms: u64) {
+        self.now_ms += ms;
+    }
+}
+
+/// 不显示画面，只记录渲染次数
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NullRenderer {
+    pub frames: usize,
+}
+
+impl Renderer for NullRenderer {
+    fn render(&mut self, _game: &mut ReplayGame, _hint: Option<&Piece>) {
+        self.frames += 1;
+    }
+}
+
+/// 保存在内存中的存储
+#[derive(Default)]
+pub struct MemoryStorage {
+    pub replays: Vec<Replay>,
+    pub saved_game: Option<(ReplayGame, Replay)>,
+}
+
+impl Storage for MemoryStorage {
+    fn save_replay(&mut self, replay: &Replay) -> Result<(), ()> {
+        self.replays.push(Replay::decode(&replay.encode()).ok_or(())?);
+        Ok(())
+    }
+
+    fn save_game(&mut self, game: &ReplayGame, replay: &Replay) -> Result<(), ()> {
+        // 与写入文件一样经过编码，保证存档可以完整恢复
+        self.saved_game = Some(decode_save(&encode_save(&(game, replay))).ok_or(())?);
+        Ok(())
+    }
+
+    fn take_saved_game(&mut self) -> Option<(ReplayGame, Replay)> {
+        self.saved_game.take()
+    }
+
+    fn has_saved_game(&mut self) -> bool {
+        self.saved_game.is_some()
+    }
+}
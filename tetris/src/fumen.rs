@@ -0,0 +1,573 @@
+//! ## Fumen v115
+//!
+//! 社区常用的 fumen 编码（https://harddrop.com/fumen/ ，https://github.com/knewjade/tetris-fumen ）。
+//! 支持版面、当前四连方块和注释，不支持 quiz 等扩展。
+//!
+//! fumen 的版面固定为 10 列 23 行，外加最下方 1 行垃圾行。导入时 fumen 的最下面一行对齐版面的最下面一行。
+//!
+//! ## 标准坐标
+//!
+//! fumen 和 TBP 等外部格式使用 SRS 旋转中心表示方块位置：x 向右，y 向上，版面最下面一行为 y = 0。
+//! 方块按名称（`IJLOSTZ`）匹配 PieceData::name，朝向与本库相同。
+
+use crate::{char_to_color_id, color_id_to_char, Board, Piece, PieceData};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// 以 SRS 旋转中心表示的方块位置
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CenterPosition {
+    /// 方块名称：`IJLOSTZ` 之一
+    pub name: char,
+    /// 朝向
+    pub orientation: usize,
+    /// 旋转中心 x，向右为正
+    pub x: isize,
+    /// 旋转中心 y，向上为正，版面最下面一行为 0
+    pub y: isize,
+}
+
+/// 标准方块在初始朝向下相对旋转中心的小块偏移（y 向上）
+fn standard_cells(name: char, orientation: usize) -> Option<[(isize, isize); 4]> {
+    let mut cells = match name {
+        'I' => [(0, 0), (-1, 0), (1, 0), (2, 0)],
+        'T' => [(0, 0), (-1, 0), (1, 0), (0, 1)],
+        'O' => [(0, 0), (1, 0), (0, 1), (1, 1)],
+        'L' => [(0, 0), (-1, 0), (1, 0), (1, 1)],
+        'J' => [(0, 0), (-1, 0), (1, 0), (-1, 1)],
+        'S' => [(0, 0), (-1, 0), (0, 1), (1, 1)],
+        'Z' => [(0, 0), (1, 0), (0, 1), (-1, 1)],
+        _ => return None,
+    };
+    for _ in 0..orientation % 4 {
+        for cell in cells.iter_mut() {
+            *cell = (cell.1, -cell.0);
+        }
+    }
+    Some(cells)
+}
+
+/// 将方块的小块坐标排序，并计算从 from 平移到 to 的偏移。两组小块形状不同时返回 None
+fn match_cells(mut from: Vec<(isize, isize)>, mut to: Vec<(isize, isize)>) -> Option<(isize, isize)> {
+    from.sort();
+    to.sort();
+    let (first_from, first_to) = (from.first()?, to.first()?);
+    let offset = (first_to.0 - first_from.0, first_to.1 - first_from.1);
+    if from.len() == to.len() && from.iter().zip(to.iter()).all(|(a, b)| (a.0 + offset.0, a.1 + offset.1) == *b) {
+        Some(offset)
+    } else {
+        None
+    }
+}
+
+/// 将方块转为标准坐标
+///
+/// # Arguments
+///
+/// * `piece_data`: 四连方块数据表
+/// * `piece`: 方块
+/// * `height`: 版面高度
+pub fn to_center_position(piece_data: &[PieceData], piece: &Piece, height: usize) -> Option<CenterPosition> {
+    let data = piece_data.get(piece.typ)?;
+    let name = data.name.chars().next()?;
+    let cells = data.orientation().get(piece.orientation)?
+        .iter()
+        .map(|(dx, dy)| (piece.position.0 + *dx as isize, height as isize - 1 - (piece.position.1 + *dy as isize)))
+        .collect();
+    let (x, y) = match_cells(standard_cells(name, piece.orientation)?.to_vec(), cells)?;
+    Some(CenterPosition {
+        name,
+        orientation: piece.orientation,
+        x,
+        y,
+    })
+}
+
+/// 将标准坐标转为方块
+///
+/// # Arguments
+///
+/// * `piece_data`: 四连方块数据表
+/// * `center`: 标准坐标
+/// * `height`: 版面高度
+pub fn from_center_position(piece_data: &[PieceData], center: &CenterPosition, height: usize) -> Option<Piece> {
+    let typ = piece_data.iter().position(|data| data.name.starts_with(center.name))?;
+    let cells = standard_cells(center.name, center.orientation)?
+        .iter()
+        .map(|(dx, dy)| (center.x + dx, height as isize - 1 - (center.y + dy)))
+        .collect();
+    let data_cells = piece_data[typ].orientation().get(center.orientation)?
+        .iter()
+        .map(|(dx, dy)| (*dx as isize, *dy as isize))
+        .collect();
+    let position = match_cells(data_cells, cells)?;
+    Some(Piece {
+        typ,
+        position,
+        orientation: center.orientation,
+    })
+}
+
+/// fumen 中的一页
+pub struct FumenPage {
+    /// 版面（不含当前方块）
+    pub board: Board,
+    /// 当前方块
+    pub piece: Option<Piece>,
+    /// 注释
+    pub comment: String,
+}
+
+const ENCODE_TABLE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const COMMENT_TABLE: &[u8] = b" !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+/// fumen 中的方块类型，下标为 fumen 编号。0 为空，8 为垃圾行
+const FUMEN_PIECES: &[u8] = b"_ILOZTJSG";
+const FIELD_WIDTH: usize = 10;
+/// 不含垃圾行的高度
+const FIELD_TOP: usize = 23;
+/// 含垃圾行的格子数
+const FIELD_BLOCKS: usize = (FIELD_TOP + 1) * FIELD_WIDTH;
+
+/// fumen 版面。第 0 行为垃圾行，第 1 行为 fumen 坐标 y = 0（最下面一行），依此类推
+type Field = [[u8; FIELD_WIDTH]; FIELD_TOP + 1];
+
+/// 将版面转为 fumen 版面，版面最下面一行对齐 fumen 的 y = 0
+fn board_to_field(board: &Board) -> Field {
+    let mut field = [[0; FIELD_WIDTH]; FIELD_TOP + 1];
+    for (row, cells) in field.iter_mut().enumerate().skip(1) {
+        if let Some(y) = (board.height + 1).checked_sub(row + 1) {
+            for (x, cell) in cells.iter_mut().enumerate() {
+                if let Some(Some(color_id)) = board.get_cell(x, y) {
+                    *cell = FUMEN_PIECES.iter().position(|c| *c as char == color_id_to_char(*color_id)).unwrap_or(8) as u8;
+                }
+            }
+        }
+    }
+    field
+}
+
+/// 将 fumen 版面转为指定高度的版面，忽略垃圾行
+fn field_to_board(field: &Field, height: usize) -> Board {
+    let mut board = Board::new(FIELD_WIDTH, height);
+    for (row, cells) in field.iter().enumerate().skip(1) {
+        if let Some(y) = (height + 1).checked_sub(row + 1) {
+            for (x, cell) in cells.iter().enumerate() {
+                if *cell != 0 {
+                    board.set_cell(x, y, char_to_color_id(FUMEN_PIECES[*cell as usize] as char));
+                }
+            }
+        }
+    }
+    board
+}
+
+/// fumen 中的方块：类型编号、旋转编号、fumen 坐标
+struct FumenPiece {
+    typ: usize,
+    rotation: usize,
+    x: isize,
+    y: isize,
+}
+
+impl FumenPiece {
+    /// fumen 的旋转编号：0 为 180 度，1 为向右，2 为初始方向，3 为向左
+    fn from_orientation(orientation: usize) -> usize {
+        [2, 1, 0, 3][orientation % 4]
+    }
+
+    fn orientation(&self) -> usize {
+        [2, 1, 0, 3][self.rotation]
+    }
+
+    fn name(&self) -> char {
+        FUMEN_PIECES[self.typ] as char
+    }
+
+    /// v115 中部分方块记录的坐标不是旋转中心，解码时需要加上这个修正，编码时减去
+    fn adjustment(&self) -> (isize, isize) {
+        match (self.name(), self.orientation()) {
+            ('O', 3) => (1, -1),
+            ('O', 2) => (1, 0),
+            ('O', 0) => (0, -1),
+            ('I', 2) => (1, 0),
+            ('I', 3) => (0, -1),
+            ('S', 0) => (0, -1),
+            ('S', 1) => (-1, 0),
+            ('Z', 0) => (0, -1),
+            ('Z', 3) => (1, 0),
+            _ => (0, 0),
+        }
+    }
+
+    fn cells(&self) -> Option<[(isize, isize); 4]> {
+        let mut cells = standard_cells(self.name(), self.orientation())?;
+        for cell in cells.iter_mut() {
+            *cell = (cell.0 + self.x, cell.1 + self.y);
+        }
+        Some(cells)
+    }
+
+    /// 将方块放入 fumen 版面，超出版面的小块忽略
+    fn put(&self, field: &mut Field) {
+        if let Some(cells) = self.cells() {
+            for (x, y) in cells {
+                if (0..FIELD_WIDTH as isize).contains(&x) && (0..FIELD_TOP as isize).contains(&y) {
+                    field[y as usize + 1][x as usize] = self.typ as u8;
+                }
+            }
+        }
+    }
+}
+
+/// 按 fumen 的规则锁定：放入方块，消除填满的行，然后处理垃圾行上升和镜像
+fn lock_field(field: &mut Field, piece: Option<&FumenPiece>, rise: bool, mirror: bool) {
+    if let Some(piece) = piece {
+        piece.put(field);
+    }
+    let mut dst = 1;
+    for src in 1..=FIELD_TOP {
+        if field[src].contains(&0) {
+            field[dst] = field[src];
+            dst += 1;
+        }
+    }
+    for row in field.iter_mut().skip(dst) {
+        *row = [0; FIELD_WIDTH];
+    }
+    if rise {
+        field.copy_within(0..FIELD_TOP, 1);
+        field[0] = [0; FIELD_WIDTH];
+    }
+    if mirror {
+        for row in field.iter_mut().skip(1) {
+            row.reverse();
+        }
+    }
+}
+
+/// JavaScript 的 escape()
+fn escape(text: &str) -> String {
+    let mut result = String::new();
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() || "@*_+-./".contains(c) {
+            result.push(c);
+        } else {
+            let mut units = [0; 2];
+            for unit in c.encode_utf16(&mut units) {
+                if *unit < 0x100 {
+                    result.push_str(&alloc::format!("%{:02X}", unit));
+                } else {
+                    result.push_str(&alloc::format!("%u{:04X}", unit));
+                }
+            }
+        }
+    }
+    result
+}
+
+/// JavaScript 的 unescape()
+fn unescape(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let hex = |digits: &[char]| -> Option<u16> {
+        let text: String = digits.iter().collect();
+        if digits.iter().all(|c| c.is_ascii_hexdigit()) {
+            u16::from_str_radix(&text, 16).ok()
+        } else {
+            None
+        }
+    };
+    let mut units = Vec::with_capacity(chars.len());
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '%' {
+            if chars.get(i + 1) == Some(&'u') && i + 6 <= chars.len() {
+                if let Some(unit) = hex(&chars[i + 2..i + 6]) {
+                    units.push(unit);
+                    i += 6;
+                    continue;
+                }
+            }
+            if i + 3 <= chars.len() {
+                if let Some(unit) = hex(&chars[i + 1..i + 3]) {
+                    units.push(unit);
+                    i += 3;
+                    continue;
+                }
+            }
+        }
+        let mut buf = [0; 2];
+        units.extend_from_slice(chars[i].encode_utf16(&mut buf));
+        i += 1;
+    }
+    char::decode_utf16(units).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)).collect()
+}
+
+/// fumen 数据写入器：每个数字用若干个 64 进制字符表示，低位在前
+struct FumenWriter {
+    values: Vec<u8>,
+}
+
+impl FumenWriter {
+    fn push(&mut self, mut value: usize, digits: usize) {
+        for _ in 0..digits {
+            self.values.push((value % 64) as u8);
+            value /= 64;
+        }
+    }
+}
+
+/// fumen 数据读取器
+struct FumenReader {
+    values: Vec<u8>,
+    index: usize,
+}
+
+impl FumenReader {
+    fn poll(&mut self, digits: usize) -> Option<usize> {
+        let mut value = 0;
+        for i in 0..digits {
+            value += *self.values.get(self.index)? as usize * 64usize.pow(i as u32);
+            self.index += 1;
+        }
+        Some(value)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.index >= self.values.len()
+    }
+}
+
+/// 编码为 fumen v115 字符串（带 `v115@` 前缀）
+///
+/// 版面宽度超过 10 的部分和高度超过 23 的部分会被丢弃，无法用标准方块表示的当前方块会被忽略
+pub fn encode_fumen(pages: &[FumenPage], piece_data: &[PieceData]) -> String {
+    let mut writer = FumenWriter { values: Vec::new() };
+    let mut prev_field = [[0; FIELD_WIDTH]; FIELD_TOP + 1];
+    let mut prev_comment = String::new();
+    let mut last_repeat_index = None;
+    for (page_index, page) in pages.iter().enumerate() {
+        let field = board_to_field(&page.board);
+
+        // 版面：与上一页的差值 (0..=16)，按行程编码
+        let diff = |i: usize| {
+            let (row, x) = (FIELD_TOP - i / FIELD_WIDTH, i % FIELD_WIDTH);
+            field[row][x] as usize + 8 - prev_field[row][x] as usize
+        };
+        let mut runs = Vec::new();
+        let mut run_diff = diff(0);
+        let mut run_length = 0;
+        for i in 1..FIELD_BLOCKS {
+            let d = diff(i);
+            if d != run_diff {
+                runs.push((run_diff, run_length));
+                run_diff = d;
+                run_length = 0;
+            } else {
+                run_length += 1;
+            }
+        }
+        runs.push((run_diff, run_length));
+        let changed = runs.len() > 1 || run_diff != 8;
+        match last_repeat_index {
+            Some(index) if !changed && writer.values[index] < 63 => {
+                writer.values[index] += 1;
+            }
+            _ => {
+                for (d, length) in &runs {
+                    writer.push(d * FIELD_BLOCKS + length, 2);
+                }
+                if changed {
+                    last_repeat_index = None;
+                } else {
+                    writer.push(0, 1);
+                    last_repeat_index = Some(writer.values.len() - 1);
+                }
+            }
+        }
+
+        // 操作：方块、旋转、坐标和各种标志
+        let piece = page.piece.as_ref()
+            .and_then(|piece| to_center_position(piece_data, piece, page.board.height))
+            .and_then(|center| {
+                let typ = FUMEN_PIECES.iter().position(|c| *c as char == center.name)?;
+                Some(FumenPiece { typ, rotation: FumenPiece::from_orientation(center.orientation), x: center.x, y: center.y })
+            });
+        let position = piece.as_ref().and_then(|piece| {
+            let (dx, dy) = piece.adjustment();
+            let (x, y) = (piece.x - dx, piece.y - dy);
+            if (0..FIELD_WIDTH as isize).contains(&x) && (-1..FIELD_TOP as isize).contains(&y) {
+                Some((FIELD_TOP as isize - y - 1) as usize * FIELD_WIDTH + x as usize)
+            } else {
+                None
+            }
+        });
+        let piece = piece.filter(|_| position.is_some());
+        let comment = escape(&page.comment);
+        let comment_changed = if page_index == 0 { !comment.is_empty() } else { comment != prev_comment };
+        let (typ, rotation, position) = match (&piece, position) {
+            (Some(piece), Some(position)) => (piece.typ, piece.rotation, position),
+            _ => (0, 0, 0),
+        };
+        let lock = true;
+        let colorize = true;
+        let mut action = !lock as usize;
+        action = action * 2 + comment_changed as usize;
+        action = action * 2 + colorize as usize;
+        action *= 2; // mirror
+        action *= 2; // rise
+        action = action * FIELD_BLOCKS + position;
+        action = action * 4 + rotation;
+        action = action * 8 + typ;
+        writer.push(action, 3);
+
+        // 注释：长度和每 4 个字符一组的编码
+        if comment_changed {
+            let values: Vec<usize> = comment.bytes()
+                .take(4095)
+                .map(|c| COMMENT_TABLE.iter().position(|v| *v == c).unwrap_or(0))
+                .collect();
+            writer.push(values.len(), 2);
+            for chunk in values.chunks(4) {
+                let value = chunk.iter().rev().fold(0, |value, c| value * (COMMENT_TABLE.len() + 1) + c);
+                writer.push(value, 5);
+            }
+            prev_comment = comment;
+        }
+
+        // 锁定之后的版面作为下一页的基准
+        let mut next_field = field;
+        if lock {
+            lock_field(&mut next_field, piece.as_ref(), false, false);
+        }
+        prev_field = next_field;
+    }
+
+    let data: String = writer.values.iter().map(|v| ENCODE_TABLE[*v as usize] as char).collect();
+    let mut result = String::from("v115@");
+    for (i, c) in data.chars().enumerate() {
+        if i >= 42 && (i - 42) % 47 == 0 {
+            result.push('?');
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// 解码 fumen v115 字符串，可以带 URL 前缀
+///
+/// # Arguments
+///
+/// * `data`: fumen 字符串
+/// * `piece_data`: 四连方块数据表，用于匹配当前方块
+/// * `height`: 版面高度
+///
+/// returns: Option<Vec<FumenPage>> 每一页的版面、当前方块和注释。格式错误时返回 None
+pub fn decode_fumen(data: &str, piece_data: &[PieceData], height: usize) -> Option<Vec<FumenPage>> {
+    let start = ["v115@", "m115@", "d115@"].iter().filter_map(|prefix| data.find(prefix)).min()?;
+    let mut reader = FumenReader {
+        values: Vec::new(),
+        index: 0,
+    };
+    for c in data[start + 5..].bytes() {
+        if c == b'?' || c.is_ascii_whitespace() {
+            continue;
+        }
+        reader.values.push(ENCODE_TABLE.iter().position(|v| *v == c)? as u8);
+    }
+
+    let mut pages = Vec::new();
+    let mut prev_field = [[0; FIELD_WIDTH]; FIELD_TOP + 1];
+    let mut prev_comment = String::new();
+    let mut repeat_count = 0;
+    while !reader.is_empty() {
+        // 版面
+        let mut field = prev_field;
+        if repeat_count > 0 {
+            repeat_count -= 1;
+        } else {
+            let mut index = 0;
+            while index < FIELD_BLOCKS {
+                let value = reader.poll(2)?;
+                let (diff, count) = (value / FIELD_BLOCKS, value % FIELD_BLOCKS + 1);
+                if diff > 16 || index + count > FIELD_BLOCKS {
+                    return None;
+                }
+                if diff == 8 && count == FIELD_BLOCKS {
+                    repeat_count = reader.poll(1)?;
+                }
+                for i in index..index + count {
+                    let cell = &mut field[FIELD_TOP - i / FIELD_WIDTH][i % FIELD_WIDTH];
+                    *cell = (*cell as usize + diff).checked_sub(8).filter(|v| *v < FUMEN_PIECES.len())? as u8;
+                }
+                index += count;
+            }
+        }
+
+        // 操作
+        let mut action = reader.poll(3)?;
+        let typ = action % 8;
+        action /= 8;
+        let rotation = action % 4;
+        action /= 4;
+        let position = action % FIELD_BLOCKS;
+        action /= FIELD_BLOCKS;
+        let rise = action % 2 == 1;
+        action /= 2;
+        let mirror = action % 2 == 1;
+        action /= 2;
+        action /= 2; // colorize
+        let has_comment = action % 2 == 1;
+        action /= 2;
+        let lock = action % 2 == 0;
+
+        let piece = if typ != 0 {
+            let mut piece = FumenPiece {
+                typ,
+                rotation,
+                x: (position % FIELD_WIDTH) as isize,
+                y: FIELD_TOP as isize - (position / FIELD_WIDTH) as isize - 1,
+            };
+            let (dx, dy) = piece.adjustment();
+            piece.x += dx;
+            piece.y += dy;
+            Some(piece)
+        } else {
+            None
+        };
+
+        // 注释
+        if has_comment {
+            let length = reader.poll(2)?;
+            let mut comment = String::with_capacity(length + 3);
+            for _ in 0..length.div_ceil(4) {
+                let mut value = reader.poll(5)?;
+                for _ in 0..4 {
+                    comment.push(COMMENT_TABLE.get(value % (COMMENT_TABLE.len() + 1)).copied().unwrap_or(b' ') as char);
+                    value /= COMMENT_TABLE.len() + 1;
+                }
+            }
+            comment.truncate(length);
+            prev_comment = unescape(&comment);
+        }
+
+        pages.push(FumenPage {
+            board: field_to_board(&field, height),
+            piece: piece.as_ref().and_then(|piece| {
+                let center = CenterPosition {
+                    name: piece.name(),
+                    orientation: piece.orientation(),
+                    x: piece.x,
+                    y: piece.y,
+                };
+                from_center_position(piece_data, &center, height)
+            }),
+            comment: prev_comment.clone(),
+        });
+
+        if lock {
+            lock_field(&mut field, piece.as_ref(), rise, mirror);
+        }
+        prev_field = field;
+    }
+    Some(pages)
+}
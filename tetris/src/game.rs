@@ -1,5 +1,9 @@
-use crate::board::ExtendedBoard;
+use crate::board::{Board, ExtendedBoard, Piece};
+use crate::bot::is_t_spin;
+use crate::movegen::{find_path_with_inputs, Input};
+use alloc::vec::Vec;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Event {
     /// Tick 事件，每帧需要调用一次。
     /// Tick 会计算重力下落、软降下落、左右 Auto Shift 或者 Auto Repeat、落地锁定。
@@ -25,8 +29,13 @@ pub enum Event {
     SoftDropBegin,
     /// 软降抬起
     SoftDropEnd,
+    /// 撤销：回到上一个方块进场时的状态（仅练习模式）
+    Undo,
+    /// 重做：恢复撤销之前的状态（仅练习模式）
+    Redo,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Config {
     /// Delay Auto Shift (frames)
     pub das: f32,
@@ -40,6 +49,12 @@ pub struct Config {
     pub lock_delay: usize,
     /// 最大重置次数
     pub max_reset_times: usize,
+    /// finesse 检查时是否把按住移动到墙边（DAS）算作一次操作。不能检测按键抬起的前端应设为 false
+    pub finesse_das: bool,
+    /// finesse 训练模式：出现 finesse 错误时不锁定，当前方块回到进场位置重新放置
+    pub finesse_training: bool,
+    /// 练习模式：每次锁定时记录快照，可以撤销和重做
+    pub practice: bool,
 }
 
 impl Default for Config {
@@ -51,6 +66,9 @@ impl Default for Config {
             gravity: 0.02,
             lock_delay: 30,
             max_reset_times: 15,
+            finesse_das: true,
+            finesse_training: false,
+            practice: false,
         }
     }
 }
@@ -92,8 +110,60 @@ pub struct Game<G> {
 
     /// 已落地 tick 数
     pub land_tick_count: usize,
-    /// 已重置锁定次数
+    /// 已重置锁定次数，不超过 max_reset_times
     pub reset_times: usize,
+
+    /// 锁定时是否统计 finesse 错误。只有显示错误数的前端需要打开，训练模式总是检查
+    pub count_finesse: bool,
+    /// 当前方块已经使用的操作数（不计硬降）
+    pub piece_inputs: usize,
+    /// 上一个锁定的方块的 finesse 错误数
+    pub last_finesse_faults: usize,
+    /// 累计 finesse 错误数
+    pub finesse_faults: usize,
+
+    /// 已经锁定的方块数
+    pub pieces: usize,
+    /// 已经消除的行数
+    pub lines: usize,
+    /// 得分
+    pub score: usize,
+    /// 连续消行次数，没有消行时归零
+    pub combo: usize,
+    /// 上一次消行是消四或者 T-spin，下一次同样的消行有 Back-to-Back 加成
+    pub back_to_back: bool,
+    /// 当前方块最后一次成功的移动是旋转，用于判断 T-spin
+    pub last_move_rotation: bool,
+
+    /// 录制的事件：(tick 序号, 事件)。为 None 时不录制
+    ///
+    /// 成功的 Tick 可以由 tick 序号推出，不录制；导致游戏结束的 Tick 会录制
+    pub recording: Option<Vec<(usize, Event)>>,
+
+    /// 复制生成器，练习模式记录快照时使用。由 `enable_undo` 设置，为 None 时不记录快照，不能撤销
+    pub clone_rng: Option<fn(&G) -> G>,
+    /// 练习模式下当前方块进场时的快照
+    pub piece_snapshot: Option<Snapshot<G>>,
+    /// 练习模式下可以撤销的快照，每锁定一个方块记录一个
+    pub undo_stack: Vec<Snapshot<G>>,
+    /// 撤销后可以重做的快照，锁定新的方块时清空
+    pub redo_stack: Vec<Snapshot<G>>,
+}
+
+/// 方块进场时的游戏快照，练习模式下用于撤销和重做
+#[derive(Clone)]
+pub struct Snapshot<G> {
+    pub board: Board,
+    pub current_piece: Option<Piece>,
+    pub hold: Option<usize>,
+    pub hold_used: bool,
+    pub rng: G,
+    pub pieces: usize,
+    pub lines: usize,
+    pub score: usize,
+    pub combo: usize,
+    pub back_to_back: bool,
+    pub finesse_faults: usize,
 }
 
 impl<G> Game<G> {
@@ -115,6 +185,106 @@ impl<G> Game<G> {
             soft_drop_last_tick: 0.0,
             land_tick_count: 0,
             reset_times: 0,
+            count_finesse: false,
+            piece_inputs: 0,
+            last_finesse_faults: 0,
+            finesse_faults: 0,
+            pieces: 0,
+            lines: 0,
+            score: 0,
+            combo: 0,
+            back_to_back: false,
+            last_move_rotation: false,
+            recording: None,
+            clone_rng: None,
+            piece_snapshot: None,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+        }
+    }
+
+    /// 游戏状态的校验值，用于检查录像回放的结果是否一致
+    pub fn checksum(&self) -> u64 {
+        // FNV-1a
+        let mut hash = 0xcbf29ce484222325u64;
+        let mut write = |value: u64| {
+            for byte in value.to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        };
+        let board = &self.board.board;
+        for y in 0..board.height {
+            for x in 0..board.width {
+                write(match board.get_cell(x, y) {
+                    Some(Some(color_id)) => *color_id as u64 + 1,
+                    _ => 0,
+                });
+            }
+        }
+        if let Some(piece) = &self.board.current_piece {
+            write(piece.typ as u64);
+            write(piece.position.0 as u64);
+            write(piece.position.1 as u64);
+            write(piece.orientation as u64);
+        }
+        write(self.hold.map_or(0, |hold| hold as u64 + 1));
+        write(self.hold_used as u64);
+        write(self.current_tick as u64);
+        write(self.pieces as u64);
+        write(self.lines as u64);
+        hash
+    }
+
+    /// 复制游戏状态用于模拟，换用指定的序列产生器。不复制录制和练习模式的快照，也不统计 finesse 错误
+    pub fn with_rng<H>(&self, rng: H) -> Game<H> {
+        Game {
+            config: self.config,
+            board: self.board.clone(),
+            rng,
+            hold: self.hold,
+            hold_used: self.hold_used,
+            current_tick: self.current_tick,
+            gravity_last_tick: self.gravity_last_tick,
+            move_left_down: self.move_left_down,
+            move_right_down: self.move_right_down,
+            move_direction: self.move_direction,
+            move_last_tick: self.move_last_tick,
+            move_auto_repeat: self.move_auto_repeat,
+            soft_drop_down: self.soft_drop_down,
+            soft_drop_last_tick: self.soft_drop_last_tick,
+            land_tick_count: self.land_tick_count,
+            reset_times: self.reset_times,
+            count_finesse: false,
+            piece_inputs: self.piece_inputs,
+            last_finesse_faults: self.last_finesse_faults,
+            finesse_faults: self.finesse_faults,
+            pieces: self.pieces,
+            lines: self.lines,
+            score: self.score,
+            combo: self.combo,
+            back_to_back: self.back_to_back,
+            last_move_rotation: self.last_move_rotation,
+            recording: None,
+            clone_rng: None,
+            piece_snapshot: None,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+        }
+    }
+}
+
+impl<G> Game<G>
+    where
+        G: Iterator<Item=usize> + Clone
+{
+    /// 允许练习模式撤销和重做。快照要复制生成器，所以只有生成器能复制时才能打开
+    ///
+    /// 之后每个方块进场时记录快照；已经有当前方块时（例如读取存档之后）立即为它记录
+    pub fn enable_undo(&mut self) {
+        self.clone_rng = Some(G::clone);
+        if self.config.practice && self.piece_snapshot.is_none() {
+            self.piece_snapshot = self.snapshot();
         }
     }
 }
@@ -123,7 +293,22 @@ impl<G> Game<G>
     where
         G: Iterator<Item=usize>
 {
+    /// 处理一个事件，录制开启时记录下来
     pub fn on_event(&mut self, event: Event) -> Result<(), ()> {
+        let tick = self.current_tick;
+        let result = self.handle_event(event);
+        if let Some(recording) = &mut self.recording {
+            if event != Event::Tick || result.is_err() {
+                recording.push((tick, event));
+            }
+        }
+        result
+    }
+
+    fn handle_event(&mut self, event: Event) -> Result<(), ()> {
+        if matches!(event, Event::RotateLeft | Event::RotateRight | Event::Rotate180 | Event::SoftDropFast | Event::SoftDropBegin | Event::MoveLeftBegin | Event::MoveRightBegin) {
+            self.piece_inputs += 1;
+        }
         match event {
             Event::Tick => {
                 if self.config.gravity > 0.0 {
@@ -138,16 +323,17 @@ impl<G> Game<G>
                         if !moved {
                             break;
                         }
+                        if self.soft_drop_down {
+                            self.score += 1;
+                        }
                     }
                 }
 
                 if self.move_direction != 0 {
-                    if !self.move_auto_repeat {
-                        if self.move_last_tick + self.config.das >= self.current_tick as _ {
-                            self.move_piece((self.move_direction, 0), 0)?;
-                            self.move_last_tick = self.current_tick as _;
-                            self.move_auto_repeat = true;
-                        }
+                    if !self.move_auto_repeat && self.move_last_tick + self.config.das >= self.current_tick as _ {
+                        self.move_piece((self.move_direction, 0), 0)?;
+                        self.move_last_tick = self.current_tick as _;
+                        self.move_auto_repeat = true;
                     }
                     if self.move_auto_repeat {
                         while self.move_last_tick + self.config.arr < self.current_tick as _ {
@@ -186,7 +372,9 @@ impl<G> Game<G>
                         if let Some(hold) = self.hold {
                             self.add_type_piece(hold)?;
                         } else {
-                            self.add_next_piece()?;
+                            // 不经过 add_next_piece：快照仍然是 Hold 之前的状态
+                            let typ = self.rng.next().ok_or(())?;
+                            self.add_type_piece(typ)?;
                         }
                         self.hold = Some(current_typ);
                         self.hold_used = true;
@@ -194,11 +382,11 @@ impl<G> Game<G>
                 }
             }
             Event::HardDrop => {
-                self.board.fast_drop();
+                self.drop_to_bottom(2);
                 self.lock_and_add_next_piece()?;
             }
             Event::SoftDropFast => {
-                self.board.fast_drop();
+                self.drop_to_bottom(1);
                 self.land_tick_count = 0;
             }
             Event::Forfeit => {
@@ -246,6 +434,16 @@ impl<G> Game<G>
             Event::SoftDropEnd => {
                 self.soft_drop_down = false;
             }
+            Event::Undo => {
+                if self.config.practice {
+                    self.undo();
+                }
+            }
+            Event::Redo => {
+                if self.config.practice {
+                    self.redo();
+                }
+            }
         }
         Ok(())
     }
@@ -261,25 +459,184 @@ impl<G> Game<G>
         self.hold_used = false;
         self.land_tick_count = 0;
         self.reset_times = 0;
+        self.piece_inputs = 0;
+        self.last_move_rotation = false;
         Ok(())
     }
 
-    /// 在默认位置添加一个新块
+    /// 在默认位置添加一个新块，练习模式下记录它进场时的快照
     pub fn add_next_piece(&mut self) -> Result<(), ()> {
         if let Some(typ) = self.rng.next() {
             self.add_type_piece(typ)?;
         } else {
             return Err(());
         }
+        if self.config.practice {
+            self.piece_snapshot = self.snapshot();
+        }
         Ok(())
     }
 
     /// 锁定当前方块，清除填满的行，并添加下一个方块
+    ///
+    /// 统计 finesse 或者训练模式下，锁定前会检查 finesse。训练模式下出现错误时，当前方块回到进场位置，不锁定
     pub fn lock_and_add_next_piece(&mut self) -> Result<(), ()> {
+        if self.count_finesse || self.config.finesse_training {
+            if let Some(faults) = self.current_finesse_faults() {
+                self.last_finesse_faults = faults;
+                self.finesse_faults += faults;
+                if faults > 0 && self.config.finesse_training {
+                    if let Some(current_piece) = &self.board.current_piece {
+                        let hold_used = self.hold_used;
+                        self.add_type_piece(current_piece.typ)?;
+                        self.hold_used = hold_used;
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        if self.config.practice {
+            if let Some(snapshot) = self.piece_snapshot.take() {
+                self.undo_stack.push(snapshot);
+            }
+            self.redo_stack.clear();
+        }
+        let t_spin = self.is_t_spin();
         self.board.lock_piece();
-        self.board.board.clear_filled_rows();
-        self.add_next_piece()?;
-        Ok(())
+        self.pieces += 1;
+        let lines = self.board.board.clear_filled_rows().len();
+        self.lines += lines;
+        self.score += self.line_clear_score(lines, t_spin);
+        self.add_next_piece()
+    }
+
+    /// 当前状态的快照，没有调用 enable_undo 时返回 None
+    fn snapshot(&self) -> Option<Snapshot<G>> {
+        let clone_rng = self.clone_rng?;
+        Some(Snapshot {
+            board: self.board.board.clone(),
+            current_piece: self.board.current_piece.clone(),
+            hold: self.hold,
+            hold_used: self.hold_used,
+            rng: clone_rng(&self.rng),
+            pieces: self.pieces,
+            lines: self.lines,
+            score: self.score,
+            combo: self.combo,
+            back_to_back: self.back_to_back,
+            finesse_faults: self.finesse_faults,
+        })
+    }
+
+    /// 恢复快照，当前方块回到进场状态，它成为新的当前快照。松开移动和软降，与新方块进场时相同
+    fn restore(&mut self, snapshot: Snapshot<G>) {
+        self.board.board = snapshot.board;
+        self.board.current_piece = snapshot.current_piece;
+        self.hold = snapshot.hold;
+        self.hold_used = snapshot.hold_used;
+        self.rng = snapshot.rng;
+        self.pieces = snapshot.pieces;
+        self.lines = snapshot.lines;
+        self.score = snapshot.score;
+        self.combo = snapshot.combo;
+        self.back_to_back = snapshot.back_to_back;
+        self.finesse_faults = snapshot.finesse_faults;
+        self.land_tick_count = 0;
+        self.reset_times = 0;
+        self.piece_inputs = 0;
+        self.last_move_rotation = false;
+        self.move_direction = 0;
+        self.move_auto_repeat = false;
+        self.soft_drop_down = false;
+        self.last_finesse_faults = 0;
+        self.piece_snapshot = self.snapshot();
+    }
+
+    /// 撤销最后一次锁定，回到上一个方块进场时的状态
+    fn undo(&mut self) {
+        if let Some(snapshot) = self.undo_stack.pop() {
+            if let Some(current) = self.piece_snapshot.take() {
+                self.redo_stack.push(current);
+            }
+            self.restore(snapshot);
+        }
+    }
+
+    /// 重做最后一次撤销
+    fn redo(&mut self) {
+        if let Some(snapshot) = self.redo_stack.pop() {
+            if let Some(current) = self.piece_snapshot.take() {
+                self.undo_stack.push(current);
+            }
+            self.restore(snapshot);
+        }
+    }
+
+    /// 当前方块落到底时的 finesse 错误数：已经使用的操作数比从进场状态到达同一落点的最少操作数多出的部分
+    ///
+    /// 落点按占据的格子比较，不要求朝向相同：例如 O 旋转之后落下，或者 S 用另一个方向转成竖的，都算作错误。
+    /// 没有当前方块或者无法从进场状态到达时返回 None
+    pub fn current_finesse_faults(&self) -> Option<usize> {
+        let current_piece = self.board.current_piece.as_ref()?;
+        let piece_data = self.board.piece_data.get(current_piece.typ)?;
+        let spawn_position = self.board.spawn_position(current_piece.typ)?;
+        let target = ((current_piece.position.0, current_piece.position.1 + self.board.test_fast_drop()), current_piece.orientation);
+        let moves = Input::moves(true, self.config.finesse_das);
+        // find_path_with_inputs 用 PieceData::same_cells 判断是否到达目标，最短路径可以以任意朝向结束
+        let path = find_path_with_inputs(&self.board.board, piece_data, (spawn_position, piece_data.spawn_orientation), target, &moves)?;
+        // 路径最后一步是硬降
+        Some(self.piece_inputs.saturating_sub(path.len() - 1))
+    }
+
+    /// 当前方块落到底，每下落一格得 points_per_row 分
+    fn drop_to_bottom(&mut self, points_per_row: usize) {
+        let distance = self.board.test_fast_drop();
+        if distance > 0 {
+            self.board.fast_drop();
+            self.score += distance as usize * points_per_row;
+            self.last_move_rotation = false;
+        }
+    }
+
+    /// 当前方块在当前位置锁定是否算作 T-spin：判定 T-spin 的方块，最后一次移动是旋转，并且满足三角判定
+    pub fn is_t_spin(&self) -> bool {
+        if !self.last_move_rotation {
+            return false;
+        }
+        match &self.board.current_piece {
+            Some(current_piece) => {
+                let is_t = self.board.piece_data.get(current_piece.typ).is_some_and(|piece_data| piece_data.t_spin);
+                is_t && is_t_spin(&self.board.board, current_piece.position)
+            }
+            None => false,
+        }
+    }
+
+    /// 计算消行得分，并更新连击和 Back-to-Back（Tetris Guideline，不计等级）
+    fn line_clear_score(&mut self, lines: usize, t_spin: bool) -> usize {
+        let mut score = match (t_spin, lines) {
+            (false, 1) => 100,
+            (false, 2) => 300,
+            (false, 3) => 500,
+            (false, 4) => 800,
+            (true, 0) => 400,
+            (true, 1) => 800,
+            (true, 2) => 1200,
+            (true, 3) => 1600,
+            _ => 0,
+        };
+        if lines == 0 {
+            self.combo = 0;
+            return score;
+        }
+        let difficult = lines >= 4 || t_spin;
+        if difficult && self.back_to_back {
+            score = score * 3 / 2;
+        }
+        self.back_to_back = difficult;
+        score += 50 * self.combo;
+        self.combo += 1;
+        score
     }
 
     /// 水平移动、垂直移动、或者旋转当前块
@@ -293,8 +650,10 @@ impl<G> Game<G>
         let prev_is_land = self.board.is_land();
         let moved = self.board.move_piece(translation, rotation);
         if moved {
+            self.last_move_rotation = rotation != 0;
+            // 达到上限后不再增加：离开落地状态再落地时仍然立即锁定
             if prev_is_land {
-                self.reset_times += 1;
+                self.reset_times = (self.reset_times + 1).min(self.config.max_reset_times);
             }
             let is_land = self.board.is_land();
             if is_land {
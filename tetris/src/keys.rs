@@ -0,0 +1,165 @@
+//! ## 按键绑定
+//!
+//! 与平台无关的按键和游戏操作的对应关系。前端把平台的按键转为 KeyCode，再查出对应的 Control。
+
+use alloc::format;
+use alloc::string::String;
+
+/// 平台无关的按键
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyCode {
+    /// 可打印字符，字母统一为小写
+    Char(char),
+    Enter,
+    Escape,
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl KeyCode {
+    /// 可打印字符转为按键，字母转为小写。回车转为 Enter
+    pub fn from_char(c: char) -> Self {
+        match c {
+            '\r' | '\n' => KeyCode::Enter,
+            '\x1b' => KeyCode::Escape,
+            _ => KeyCode::Char(c.to_ascii_lowercase()),
+        }
+    }
+
+    /// 显示用的名称
+    pub fn name(&self) -> String {
+        match self {
+            KeyCode::Char(' ') => "SPACE".into(),
+            KeyCode::Char(c) => format!("{}", c.to_ascii_uppercase()),
+            KeyCode::Enter => "ENTER".into(),
+            KeyCode::Escape => "ESC".into(),
+            KeyCode::Up => "UP".into(),
+            KeyCode::Down => "DOWN".into(),
+            KeyCode::Left => "LEFT".into(),
+            KeyCode::Right => "RIGHT".into(),
+        }
+    }
+}
+
+/// 可以绑定按键的操作
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Control {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    HardDrop,
+    RotateLeft,
+    RotateRight,
+    Rotate180,
+    Hold,
+    /// 放弃这一局
+    Forfeit,
+    /// 切换落点提示
+    Hint,
+    /// 暂停并保存
+    Suspend,
+    Undo,
+    Redo,
+}
+
+impl Control {
+    /// 所有操作，顺序与按键设置菜单一致
+    pub const ALL: [Control; 13] = [
+        Control::MoveLeft,
+        Control::MoveRight,
+        Control::SoftDrop,
+        Control::HardDrop,
+        Control::RotateLeft,
+        Control::RotateRight,
+        Control::Rotate180,
+        Control::Hold,
+        Control::Forfeit,
+        Control::Hint,
+        Control::Suspend,
+        Control::Undo,
+        Control::Redo,
+    ];
+
+    /// 显示用的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Control::MoveLeft => "MOVE LEFT",
+            Control::MoveRight => "MOVE RIGHT",
+            Control::SoftDrop => "SOFT DROP",
+            Control::HardDrop => "HARD DROP",
+            Control::RotateLeft => "ROTATE LEFT",
+            Control::RotateRight => "ROTATE RIGHT",
+            Control::Rotate180 => "ROTATE 180",
+            Control::Hold => "HOLD",
+            Control::Forfeit => "RESET",
+            Control::Hint => "HINT",
+            Control::Suspend => "SUSPEND",
+            Control::Undo => "UNDO",
+            Control::Redo => "REDO",
+        }
+    }
+
+    fn index(&self) -> usize {
+        Control::ALL.iter().position(|control| control == self).unwrap_or(0)
+    }
+}
+
+/// 每个操作对应一个按键，每个按键最多对应一个操作
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyBindings {
+    /// 下标与 Control::ALL 一致
+    keys: [KeyCode; Control::ALL.len()],
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            keys: [
+                KeyCode::Left,
+                KeyCode::Right,
+                KeyCode::Down,
+                KeyCode::Char(' '),
+                KeyCode::Char('x'),
+                KeyCode::Char('c'),
+                KeyCode::Char('s'),
+                KeyCode::Char('z'),
+                KeyCode::Char('r'),
+                KeyCode::Char('h'),
+                KeyCode::Char('q'),
+                KeyCode::Char('u'),
+                KeyCode::Char('y'),
+            ],
+        }
+    }
+}
+
+impl KeyBindings {
+    /// 操作绑定的按键
+    pub fn key(&self, control: Control) -> KeyCode {
+        self.keys[control.index()]
+    }
+
+    /// 按键对应的操作
+    pub fn control(&self, key: KeyCode) -> Option<Control> {
+        self.keys.iter().position(|k| *k == key).map(|index| Control::ALL[index])
+    }
+
+    /// 给操作绑定按键。这个按键原来绑定的操作换成 control 原来的按键，保证每个按键只对应一个操作
+    ///
+    /// Escape 用于取消，不能绑定
+    ///
+    /// returns: bool 是否绑定成功
+    pub fn bind(&mut self, control: Control, key: KeyCode) -> bool {
+        if key == KeyCode::Escape {
+            return false;
+        }
+        let index = control.index();
+        if let Some(other) = self.keys.iter().position(|k| *k == key) {
+            self.keys[other] = self.keys[index];
+        }
+        self.keys[index] = key;
+        true
+    }
+}
@@ -1,12 +1,39 @@
 #![no_std]
+#![allow(clippy::result_unit_err)]
 extern crate alloc;
 
+pub use crate::analysis::*;
 pub use crate::board::*;
+pub use crate::bot::*;
+pub use crate::codec::*;
 pub use crate::data::*;
+pub use crate::font::*;
+pub use crate::frontend::*;
+pub use crate::fumen::*;
 pub use crate::game::*;
+pub use crate::keys::*;
+pub use crate::menu::*;
+pub use crate::movegen::*;
+pub use crate::render::*;
+pub use crate::replay::*;
 pub use crate::rng::*;
+pub use crate::save::*;
+pub use crate::text::*;
 
 mod data;
 mod rng;
 mod board;
 mod game;
+mod text;
+mod fumen;
+mod analysis;
+mod movegen;
+mod bot;
+mod codec;
+mod replay;
+mod render;
+mod save;
+mod frontend;
+mod font;
+mod keys;
+mod menu;
@@ -0,0 +1,192 @@
+//! ## 菜单
+//!
+//! 与平台无关的菜单数据和设置项。菜单只用固定的按键操作：上下选择，左右修改数值，回车确认，Esc 返回。
+
+use crate::{Config, KeyCode};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// 菜单操作
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuInput {
+    Up,
+    Down,
+    Left,
+    Right,
+    Select,
+    Back,
+}
+
+impl MenuInput {
+    /// 菜单使用的按键，不受按键绑定影响
+    pub fn from_key(key: KeyCode) -> Option<Self> {
+        match key {
+            KeyCode::Up => Some(MenuInput::Up),
+            KeyCode::Down => Some(MenuInput::Down),
+            KeyCode::Left => Some(MenuInput::Left),
+            KeyCode::Right => Some(MenuInput::Right),
+            KeyCode::Enter => Some(MenuInput::Select),
+            KeyCode::Escape => Some(MenuInput::Back),
+            KeyCode::Char(_) => None,
+        }
+    }
+}
+
+/// 菜单项
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MenuItem {
+    pub label: String,
+    /// 显示在右侧的数值，可以为空
+    pub value: String,
+    /// 禁用的项显示为灰色，不能选中
+    pub enabled: bool,
+}
+
+impl MenuItem {
+    pub fn new(label: &str) -> Self {
+        Self {
+            label: label.into(),
+            value: String::new(),
+            enabled: true,
+        }
+    }
+
+    pub fn with_value(label: &str, value: String) -> Self {
+        Self {
+            value,
+            ..Self::new(label)
+        }
+    }
+
+    pub fn disabled(self) -> Self {
+        Self {
+            enabled: false,
+            ..self
+        }
+    }
+}
+
+/// 菜单：标题、菜单项和选中项
+#[derive(Clone, Debug, Default)]
+pub struct Menu {
+    pub title: String,
+    pub items: Vec<MenuItem>,
+    pub selected: usize,
+}
+
+impl Menu {
+    /// 创建菜单，选中第一个可用的项
+    pub fn new(title: &str, items: Vec<MenuItem>) -> Self {
+        let mut menu = Self {
+            title: title.into(),
+            items: Vec::new(),
+            selected: 0,
+        };
+        menu.set_items(items);
+        menu
+    }
+
+    /// 替换菜单项（例如数值变化后），保留选中的位置。选中项不可用时往后找一个可用的
+    pub fn set_items(&mut self, items: Vec<MenuItem>) {
+        self.items = items;
+        self.selected = self.selected.min(self.items.len().saturating_sub(1));
+        if !self.items.get(self.selected).is_some_and(|item| item.enabled) {
+            self.move_selection(1);
+        }
+    }
+
+    /// 上下移动选中项，跳过不可用的项，到两端时循环。没有可用的项时不变
+    pub fn move_selection(&mut self, delta: isize) {
+        let len = self.items.len() as isize;
+        let mut index = self.selected as isize;
+        for _ in 0..len {
+            index = (index + delta).rem_euclid(len);
+            if self.items[index as usize].enabled {
+                self.selected = index as usize;
+                return;
+            }
+        }
+    }
+
+    /// 处理上下选择，其余操作返回给调用者
+    pub fn navigate(&mut self, input: MenuInput) -> Option<MenuInput> {
+        match input {
+            MenuInput::Up => self.move_selection(-1),
+            MenuInput::Down => self.move_selection(1),
+            _ => return Some(input),
+        }
+        None
+    }
+}
+
+/// 设置菜单中可以修改的 Config 项
+///
+/// 键盘只能检测按下，所以 DAS、ARR 和软降速度在 UEFI 版中不起作用，不在这里列出
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Setting {
+    Gravity,
+    LockDelay,
+    MaxResets,
+}
+
+/// 重力的可选值 (blocks per frame)
+const GRAVITY_STEPS: [f32; 10] = [0.0, 0.005, 0.01, 0.02, 0.05, 0.1, 0.2, 0.5, 1.0, 20.0];
+/// 锁定延迟的范围和步长 (frames)
+const LOCK_DELAY_MAX: usize = 120;
+const LOCK_DELAY_STEP: usize = 5;
+/// 最大重置次数的上限
+const MAX_RESETS_MAX: usize = 30;
+
+impl Setting {
+    pub const ALL: [Setting; 3] = [Setting::Gravity, Setting::LockDelay, Setting::MaxResets];
+
+    /// 显示用的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Setting::Gravity => "GRAVITY",
+            Setting::LockDelay => "LOCK DELAY",
+            Setting::MaxResets => "MAX RESETS",
+        }
+    }
+
+    /// 当前值的文字
+    pub fn value(&self, config: &Config) -> String {
+        match self {
+            Setting::Gravity => format!("{}", config.gravity),
+            Setting::LockDelay => format!("{}", config.lock_delay),
+            Setting::MaxResets => format!("{}", config.max_reset_times),
+        }
+    }
+
+    /// 往 direction 的方向（负数减小，正数增大）调整一步，到达边界后不变
+    pub fn adjust(&self, config: &mut Config, direction: isize) {
+        match self {
+            Setting::Gravity => {
+                let gravity = config.gravity;
+                let next = if direction < 0 {
+                    GRAVITY_STEPS.iter().rev().find(|step| **step < gravity)
+                } else {
+                    GRAVITY_STEPS.iter().find(|step| **step > gravity)
+                };
+                if let Some(next) = next {
+                    config.gravity = *next;
+                }
+            }
+            Setting::LockDelay => {
+                config.lock_delay = if direction < 0 {
+                    config.lock_delay.saturating_sub(LOCK_DELAY_STEP)
+                } else {
+                    (config.lock_delay + LOCK_DELAY_STEP).min(LOCK_DELAY_MAX)
+                };
+            }
+            Setting::MaxResets => {
+                config.max_reset_times = if direction < 0 {
+                    config.max_reset_times.saturating_sub(1)
+                } else {
+                    (config.max_reset_times + 1).min(MAX_RESETS_MAX)
+                };
+            }
+        }
+    }
+}
@@ -0,0 +1,374 @@
+//! ## 落点搜索
+//!
+//! 从某个状态出发，通过左右平移、软降一格和带踢墙的旋转，找出所有可以到达的锁定位置（包括需要踢墙的 tuck 和 spin）。
+//! 机器人、提示、finesse 检查都基于这里的搜索。
+//!
+//! find_path 搜索到达指定落点的最短操作序列，可以转为真实的事件序列发送给 Game。
+//! 搜索不考虑重力和锁定延迟，simulate_path 在游戏的副本上逐步模拟，保证生成的事件序列确实能到达落点。
+
+use crate::{Board, Config, Event, ExtendedBoard, Game, PieceData};
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// 一个可以锁定的落点
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Placement {
+    /// 位置
+    pub position: (isize, isize),
+    /// 方向
+    pub orientation: usize,
+    /// 能否以旋转作为锁定前的最后一步到达（用于判断 T-spin）
+    pub spin: bool,
+}
+
+/// 每一步可以尝试的操作：平移和旋转
+pub(crate) const MOVES: &[((isize, isize), usize)] = &[
+    ((-1, 0), 0),
+    ((1, 0), 0),
+    ((0, 1), 0),
+    ((0, 0), 1),
+    ((0, 0), 3),
+    ((0, 0), 2),
+];
+
+/// 搜索时记录已经访问过的状态
+pub(crate) struct StateTable {
+    margin: isize,
+    width: isize,
+    height: isize,
+    visited: Vec<bool>,
+}
+
+impl StateTable {
+    pub(crate) fn new(board: &Board, piece_data: &PieceData) -> Self {
+        // 方块的小块偏移可能让位置为负数
+        let margin = piece_data.orientation().iter()
+            .flatten()
+            .map(|(x, y)| *x.max(y) as isize + 1)
+            .max()
+            .unwrap_or(0);
+        let width = board.width as isize + margin;
+        let height = board.height as isize + margin;
+        Self {
+            margin,
+            width,
+            height,
+            visited: vec![false; (width * height) as usize * piece_data.orientation().len()],
+        }
+    }
+
+    fn index(&self, (x, y): (isize, isize), orientation: usize) -> Option<usize> {
+        let (x, y) = (x + self.margin, y + self.margin);
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        Some((orientation * self.height as usize + y as usize) * self.width as usize + x as usize)
+    }
+
+    /// 标记为已访问
+    ///
+    /// returns: bool 之前是否未访问过
+    pub(crate) fn visit(&mut self, position: (isize, isize), orientation: usize) -> bool {
+        if let Some(index) = self.index(position, orientation) {
+            if let Some(visited) = self.visited.get_mut(index) {
+                if !*visited {
+                    *visited = true;
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+/// 搜索所有可以到达的落点
+///
+/// # Arguments
+///
+/// * `board`: 版面
+/// * `piece_data`: 四连方块数据
+/// * `position`: 起始位置
+/// * `orientation`: 起始方向
+/// * `allow_180`: 是否允许 180 度旋转
+///
+/// returns: Vec<Placement> 所有落点。占据相同格子的落点只保留最先找到的一个
+pub fn reachable_placements(board: &Board, piece_data: &PieceData, position: (isize, isize), orientation: usize, allow_180: bool) -> Vec<Placement> {
+    let mut placements: Vec<Placement> = Vec::new();
+    if !board.test_piece(piece_data, position, orientation) {
+        return placements;
+    }
+    let mut add_placement = |placement: Placement| {
+        if board.test_piece(piece_data, (placement.position.0, placement.position.1 + 1), placement.orientation) {
+            return;
+        }
+        if let Some(found) = placements.iter_mut().find(|found| piece_data.same_cells((found.position, found.orientation), (placement.position, placement.orientation))) {
+            found.spin |= placement.spin;
+        } else {
+            placements.push(placement);
+        }
+    };
+
+    let mut table = StateTable::new(board, piece_data);
+    let mut queue = VecDeque::new();
+    table.visit(position, orientation);
+    queue.push_back((position, orientation));
+    add_placement(Placement {
+        position,
+        orientation,
+        spin: false,
+    });
+    while let Some((position, orientation)) = queue.pop_front() {
+        for (translation, rotation) in MOVES {
+            if *rotation == 2 && !allow_180 {
+                continue;
+            }
+            if let Some((new_position, new_orientation)) = board.test_move_piece(piece_data, position, orientation, *translation, *rotation) {
+                add_placement(Placement {
+                    position: new_position,
+                    orientation: new_orientation,
+                    spin: *rotation != 0,
+                });
+                if table.visit(new_position, new_orientation) {
+                    queue.push_back((new_position, new_orientation));
+                }
+            }
+        }
+    }
+    placements
+}
+
+impl ExtendedBoard {
+    /// 从当前四连方块的进场状态出发，搜索所有可以到达的落点。没有当前方块时返回空列表
+    pub fn reachable_placements(&self, allow_180: bool) -> Vec<Placement> {
+        if let Some(current_piece) = &self.current_piece {
+            if let (Some(piece_data), Some(position)) = (self.piece_data.get(current_piece.typ), self.spawn_position(current_piece.typ)) {
+                return reachable_placements(&self.board, piece_data, position, piece_data.spawn_orientation, allow_180);
+            }
+        }
+        Vec::new()
+    }
+}
+
+/// 玩家的一次操作，finesse 意义上的一次按键
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Input {
+    /// 左移一格
+    Left,
+    /// 右移一格
+    Right,
+    /// 按住左移，直到不能移动（DAS 到墙）
+    DasLeft,
+    /// 按住右移，直到不能移动（DAS 到墙）
+    DasRight,
+    RotateLeft,
+    RotateRight,
+    Rotate180,
+    /// 软降到底，不锁定
+    SoftDrop,
+    /// 硬降
+    HardDrop,
+}
+
+impl Input {
+    /// 搜索时尝试的全部操作（不含硬降）
+    pub const MOVES: &'static [Input] = &[
+        Input::Left,
+        Input::Right,
+        Input::DasLeft,
+        Input::DasRight,
+        Input::RotateRight,
+        Input::RotateLeft,
+        Input::Rotate180,
+        Input::SoftDrop,
+    ];
+
+    /// 选出搜索时可以使用的操作
+    ///
+    /// # Arguments
+    ///
+    /// * `allow_180`: 是否允许 180 度旋转
+    /// * `allow_das`: 是否允许按住移动到墙边。不能检测按键抬起的前端只能逐格移动
+    pub fn moves(allow_180: bool, allow_das: bool) -> Vec<Input> {
+        Input::MOVES.iter()
+            .copied()
+            .filter(|input| allow_180 || *input != Input::Rotate180)
+            .filter(|input| allow_das || !matches!(input, Input::DasLeft | Input::DasRight))
+            .collect()
+    }
+
+    /// 在版面上执行这个操作
+    ///
+    /// returns: Option<((isize, isize), usize)> 新的位置，新的朝向。没有移动时返回 None
+    pub fn apply(&self, board: &Board, piece_data: &PieceData, position: (isize, isize), orientation: usize) -> Option<((isize, isize), usize)> {
+        let repeat = |dx: isize| {
+            let mut state = board.test_move_piece(piece_data, position, orientation, (dx, 0), 0)?;
+            while let Some(next) = board.test_move_piece(piece_data, state.0, state.1, (dx, 0), 0) {
+                state = next;
+            }
+            Some(state)
+        };
+        match self {
+            Input::Left => board.test_move_piece(piece_data, position, orientation, (-1, 0), 0),
+            Input::Right => board.test_move_piece(piece_data, position, orientation, (1, 0), 0),
+            Input::DasLeft => repeat(-1),
+            Input::DasRight => repeat(1),
+            Input::RotateRight => board.test_move_piece(piece_data, position, orientation, (0, 0), 1),
+            Input::RotateLeft => board.test_move_piece(piece_data, position, orientation, (0, 0), 3),
+            Input::Rotate180 => board.test_move_piece(piece_data, position, orientation, (0, 0), 2),
+            Input::SoftDrop | Input::HardDrop => {
+                let dy = board.test_drop_distance(piece_data, position, orientation);
+                if dy > 0 {
+                    Some(((position.0, position.1 + dy), orientation))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// 转为发送给 Game::on_event 的事件序列
+    ///
+    /// DAS 在按下和抬起之间插入足够多的 Tick，保证在当前 Config 下能移动到墙边。
+    /// 只适用于没有重力的游戏：这些 Tick 中方块可能下落，落地的方块可能锁定。有重力时使用 `simulate_path`
+    pub fn to_events(&self, config: &Config, board_width: usize) -> Vec<Event> {
+        let das = |begin: Event, end: Event| {
+            let ticks = config.das.max(0.0) as usize + 1 + (config.arr.max(0.0) as usize + 1) * board_width;
+            let mut events = vec![begin];
+            events.resize(ticks + 1, Event::Tick);
+            events.push(end);
+            events
+        };
+        match self {
+            Input::Left => vec![Event::MoveLeftBegin, Event::MoveLeftEnd],
+            Input::Right => vec![Event::MoveRightBegin, Event::MoveRightEnd],
+            Input::DasLeft => das(Event::MoveLeftBegin, Event::MoveLeftEnd),
+            Input::DasRight => das(Event::MoveRightBegin, Event::MoveRightEnd),
+            Input::RotateLeft => vec![Event::RotateLeft],
+            Input::RotateRight => vec![Event::RotateRight],
+            Input::Rotate180 => vec![Event::Rotate180],
+            Input::SoftDrop => vec![Event::SoftDropFast],
+            Input::HardDrop => vec![Event::HardDrop],
+        }
+    }
+}
+
+/// 将操作序列转为事件序列。与 `Input::to_events` 一样只适用于没有重力的游戏
+pub fn inputs_to_events(inputs: &[Input], config: &Config, board_width: usize) -> Vec<Event> {
+    inputs.iter().flat_map(|input| input.to_events(config, board_width)).collect()
+}
+
+/// 方块被重力带离路径时最多重新搜索几次
+const MAX_RESEARCH_TIMES: usize = 8;
+
+/// 在游戏上模拟放置当前方块，生成到达目标落点并硬降的事件序列
+///
+/// 路径搜索不考虑重力和锁定延迟，所以逐个操作发送事件并检查方块的状态：DAS 只插入移动到墙边所需的 Tick，
+/// 方块被重力带离路径时从当前状态重新搜索。
+///
+/// # Arguments
+///
+/// * `game`: 用于模拟的游戏，通常由 `Game::with_rng` 复制得到。返回时处于硬降之前的状态
+/// * `target`: 目标落点
+/// * `moves`: 可以使用的操作
+///
+/// returns: Option<Vec<Event>> 以 HardDrop 结尾的事件序列。无法到达，或者方块在硬降之前锁定时返回 None
+pub fn simulate_path<G>(game: &mut Game<G>, target: ((isize, isize), usize), moves: &[Input]) -> Option<Vec<Event>>
+    where
+        G: Iterator<Item=usize>
+{
+    let pieces = game.pieces;
+    let mut events = Vec::new();
+    'search: for _ in 0..=MAX_RESEARCH_TIMES {
+        let current_piece = game.board.current_piece.clone()?;
+        let piece_data = game.board.piece_data.get(current_piece.typ)?.clone();
+        let path = find_path_with_inputs(&game.board.board, &piece_data, (current_piece.position, current_piece.orientation), target, moves)?;
+        for input in path {
+            if input == Input::HardDrop {
+                events.push(Event::HardDrop);
+                return Some(events);
+            }
+            let current_piece = game.board.current_piece.clone()?;
+            let expected = input.apply(&game.board.board, &piece_data, current_piece.position, current_piece.orientation)?;
+            let input_events = input.to_events(&game.config, game.board.board.width);
+            for event in input_events {
+                // DAS 到墙后不再等待
+                let arrived = game.board.current_piece.as_ref().is_none_or(|piece| piece.position.0 == expected.0.0);
+                if event == Event::Tick && arrived {
+                    continue;
+                }
+                game.on_event(event).ok()?;
+                events.push(event);
+                if game.pieces != pieces {
+                    return None;
+                }
+            }
+            let current_piece = game.board.current_piece.as_ref()?;
+            if (current_piece.position, current_piece.orientation) != expected {
+                continue 'search;
+            }
+        }
+    }
+    None
+}
+
+/// 搜索从指定状态到目标落点的最短操作序列（不考虑重力）
+///
+/// # Arguments
+///
+/// * `board`: 版面
+/// * `piece_data`: 四连方块数据
+/// * `(position, orientation)`: 起始状态
+/// * `(target_position, target_orientation)`: 目标落点，占据相同格子的其他朝向也算到达
+/// * `allow_180`: 是否允许 180 度旋转
+///
+/// returns: Option<Vec<Input>> 以 HardDrop 结尾的操作序列。无法到达时返回 None
+pub fn find_path(board: &Board, piece_data: &PieceData, from: ((isize, isize), usize), target: ((isize, isize), usize), allow_180: bool) -> Option<Vec<Input>> {
+    find_path_with_inputs(board, piece_data, from, target, &Input::moves(allow_180, true))
+}
+
+/// 只使用指定的操作，搜索从指定状态到目标落点的最短操作序列
+///
+/// * `moves`: 可以使用的操作，HardDrop 总是作为最后一步
+pub fn find_path_with_inputs(board: &Board, piece_data: &PieceData, (position, orientation): ((isize, isize), usize), target: ((isize, isize), usize), moves: &[Input]) -> Option<Vec<Input>> {
+    if !board.test_piece(piece_data, position, orientation) {
+        return None;
+    }
+    // 每个节点：状态、父节点下标、到达这个状态的操作
+    let mut nodes = vec![(position, orientation, 0, Input::HardDrop)];
+    let mut table = StateTable::new(board, piece_data);
+    table.visit(position, orientation);
+    let mut index = 0;
+    while index < nodes.len() {
+        let (position, orientation, _, _) = nodes[index];
+        let dy = board.test_drop_distance(piece_data, position, orientation);
+        if piece_data.same_cells(((position.0, position.1 + dy), orientation), target) {
+            let mut inputs = vec![Input::HardDrop];
+            while index != 0 {
+                let (_, _, parent, input) = nodes[index];
+                inputs.push(input);
+                index = parent;
+            }
+            inputs.reverse();
+            return Some(inputs);
+        }
+        for input in moves {
+            if let Some((new_position, new_orientation)) = input.apply(board, piece_data, position, orientation) {
+                if table.visit(new_position, new_orientation) {
+                    nodes.push((new_position, new_orientation, index, *input));
+                }
+            }
+        }
+        index += 1;
+    }
+    None
+}
+
+impl ExtendedBoard {
+    /// 搜索当前四连方块从当前状态到目标落点的最短操作序列
+    pub fn find_path(&self, target: ((isize, isize), usize), allow_180: bool) -> Option<Vec<Input>> {
+        let current_piece = self.current_piece.as_ref()?;
+        let piece_data = self.piece_data.get(current_piece.typ)?;
+        find_path(&self.board, piece_data, (current_piece.position, current_piece.orientation), target, allow_180)
+    }
+}
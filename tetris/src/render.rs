@@ -0,0 +1,327 @@
+//! ## 渲染
+//!
+//! 与平台无关的画面布局和绘制。绘制目标是实现了 Framebuffer 的 RGB 缓冲区：UEFI 版使用 GOP 的缓冲区，宿主机可以输出图片文件。
+
+use crate::{draw_text, text_width, Game, Menu, Piece, PreviewGenerator, GLYPH_HEIGHT, GLYPH_SPACING, GLYPH_WIDTH, TICK_MS};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// RGB 颜色
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+}
+
+/// RGB 帧缓冲区
+pub trait Framebuffer {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    /// 填充矩形，超出缓冲区的部分忽略
+    fn fill_rect(&mut self, x: usize, y: usize, cx: usize, cy: usize, color: Rgb);
+}
+
+/// 颜色表，下标为 PieceData::color_id
+pub const COLOR_TABLE: &[Rgb] = &[
+    Rgb::new(15, 155, 215), // 青 I
+    Rgb::new(33, 65, 198), // 蓝 J
+    Rgb::new(227, 91, 2), // 橙 L
+    Rgb::new(227, 159, 2), // 黄 O
+    Rgb::new(89, 177, 1), // 绿 S
+    Rgb::new(175, 41, 138), // 紫 T
+    Rgb::new(215, 15, 55), // 红 Z
+    Rgb::new(128, 128, 128), // 灰 垃圾行
+];
+
+/// HUD 标签文字的颜色
+const LABEL_COLOR: Rgb = Rgb::new(160, 160, 160);
+/// HUD 数值文字的颜色
+const VALUE_COLOR: Rgb = Rgb::new(255, 255, 255);
+/// 菜单选中项的颜色
+const SELECTED_COLOR: Rgb = Rgb::new(227, 159, 2);
+/// 菜单中不可用的项的颜色
+const DISABLED_COLOR: Rgb = Rgb::new(80, 80, 80);
+
+/// 标题文字的点阵，每个字母 5x5
+const TITLE: [[&str; 5]; 6] = [
+    ["#####", "..#..", "..#..", "..#..", "..#.."],
+    ["#####", "#....", "####.", "#....", "#####"],
+    ["#####", "..#..", "..#..", "..#..", "..#.."],
+    ["####.", "#...#", "####.", "#..#.", "#...#"],
+    ["#####", "..#..", "..#..", "..#..", "#####"],
+    [".####", "#....", ".###.", "....#", "####."],
+];
+
+/// 绘制开始界面：标题、进度条和主菜单
+///
+/// # Arguments
+///
+/// * `buffer`: 缓冲区
+/// * `remaining`: 进入演示前剩余时间的比例，显示为标题下方的进度条
+/// * `menu`: 主菜单，显示在进度条下方
+pub fn render_title<F: Framebuffer>(buffer: &mut F, remaining: f32, menu: &Menu) {
+    let cell_size = buffer.width() / 50;
+    let title_width = (TITLE.len() * 6 - 1) * cell_size;
+    let start_x = buffer.width() / 2 - title_width / 2;
+    let start_y = buffer.height() / 6;
+    buffer.fill_rect(0, 0, buffer.width(), buffer.height(), Rgb::new(0, 0, 0));
+    for (i, letter) in TITLE.iter().enumerate() {
+        let color = COLOR_TABLE[i % 7];
+        for (y_index, row) in letter.iter().enumerate() {
+            for (x_index, c) in row.chars().enumerate() {
+                if c == '#' {
+                    let x = start_x + (i * 6 + x_index) * cell_size;
+                    let y = start_y + y_index * cell_size;
+                    buffer.fill_rect(x, y, cell_size.saturating_sub(1), cell_size.saturating_sub(1), color);
+                }
+            }
+        }
+    }
+    let bar_y = start_y + 7 * cell_size;
+    let bar_width = (title_width as f32 * remaining.clamp(0.0, 1.0)) as usize;
+    buffer.fill_rect(start_x, bar_y, bar_width, cell_size / 4 + 1, Rgb::new(255, 255, 255));
+    render_menu_items(buffer, menu, bar_y + 2 * cell_size, menu_scale(buffer));
+}
+
+/// 绘制菜单界面：标题、菜单项，以及底部的操作说明
+pub fn render_menu<F: Framebuffer>(buffer: &mut F, menu: &Menu) {
+    let scale = menu_scale(buffer);
+    buffer.fill_rect(0, 0, buffer.width(), buffer.height(), Rgb::new(0, 0, 0));
+    let title_y = buffer.height() / 8;
+    let x = buffer.width().saturating_sub(text_width(&menu.title, 2 * scale)) / 2;
+    draw_text(buffer, (x, title_y), 2 * scale, &menu.title, VALUE_COLOR);
+    render_menu_items(buffer, menu, title_y + (GLYPH_HEIGHT + 8) * 2 * scale, scale);
+    let help = "UP DOWN SELECT  LEFT RIGHT CHANGE  ENTER OK  ESC BACK";
+    let x = buffer.width().saturating_sub(text_width(help, scale)) / 2;
+    let y = buffer.height().saturating_sub((GLYPH_HEIGHT + 8) * scale);
+    draw_text(buffer, (x, y), scale, help, LABEL_COLOR);
+}
+
+/// 菜单文字的放大倍数，480 行的屏幕为 2 倍
+fn menu_scale<F: Framebuffer>(buffer: &F) -> usize {
+    (buffer.height() / 240).max(1)
+}
+
+/// 从 top 开始每行绘制一个菜单项，整体水平居中。数值在名称右侧对齐成一列，选中项左侧显示 `>`
+fn render_menu_items<F: Framebuffer>(buffer: &mut F, menu: &Menu, top: usize, scale: usize) {
+    let label_len = menu.items.iter().map(|item| item.label.chars().count()).max().unwrap_or(0);
+    let lines: Vec<String> = menu.items.iter().map(|item| {
+        if item.value.is_empty() {
+            item.label.clone()
+        } else {
+            format!("{:<width$}  {}", item.label, item.value, width = label_len)
+        }
+    }).collect();
+    let block_width = lines.iter().map(|line| text_width(line, scale)).max().unwrap_or(0);
+    let left = buffer.width().saturating_sub(block_width) / 2;
+    let line_height = (GLYPH_HEIGHT + 4) * scale;
+    for (i, (item, line)) in menu.items.iter().zip(&lines).enumerate() {
+        let y = top + i * line_height;
+        let color = if i == menu.selected {
+            SELECTED_COLOR
+        } else if item.enabled {
+            VALUE_COLOR
+        } else {
+            DISABLED_COLOR
+        };
+        if i == menu.selected {
+            let marker_x = left.saturating_sub(2 * (GLYPH_WIDTH + GLYPH_SPACING) * scale);
+            draw_text(buffer, (marker_x, y), scale, ">", color);
+        }
+        draw_text(buffer, (left, y), scale, line, color);
+    }
+}
+
+/// 绘制游戏画面
+///
+/// * `hint`: 提示的落点，画成空心的方块
+/// * `seed`: 随机种子，显示在版面下方
+pub fn render_game<F, G>(game: &mut Game<PreviewGenerator<usize, G>>, buffer: &mut F, hint: Option<&Piece>, seed: Option<u32>)
+    where
+        F: Framebuffer,
+        G: Iterator<Item=usize>
+{
+    // 左边留出暂存区，右边留出预览区，各 6 格
+    let cell_size = (buffer.height() / (game.board.board.height + 4)).min(buffer.width() / (game.board.board.width + 12));
+    let start_x = buffer.width() / 2 - game.board.board.width * cell_size / 2;
+    let start_y = buffer.height() / 2 - game.board.board.height * cell_size / 2;
+    for y_index in 0..game.board.board.height {
+        for x_index in 0..game.board.board.width {
+            let x = start_x + x_index * cell_size;
+            let y = start_y + y_index * cell_size;
+            if let Some(cell) = game.board.board.get_cell(x_index, y_index) {
+                if let Some(typ) = cell {
+                    buffer.fill_rect(x, y, cell_size, cell_size, COLOR_TABLE[*typ]);
+                } else {
+                    buffer.fill_rect(x, y, cell_size, cell_size, Rgb::new(0, 0, 0));
+                }
+            }
+        }
+    }
+    if let Some(current_piece) = &game.board.current_piece {
+        if let Some(piece_data) = game.board.piece_data.get(current_piece.typ) {
+            // 半透明颜色
+            let x_index = current_piece.position.0;
+            let y_index = current_piece.position.1 + game.board.test_fast_drop();
+            let color = COLOR_TABLE[piece_data.color_id];
+            let color = Rgb::new(color.r / 2, color.g / 2, color.b / 2);
+            for (x_offset, y_offset) in &piece_data.orientation()[current_piece.orientation] {
+                let x = start_x as isize + (x_index + *x_offset as isize) * cell_size as isize;
+                let y = start_y as isize + (y_index + *y_offset as isize) * cell_size as isize;
+                fill_rect_signed(buffer, x, y, cell_size, cell_size, color);
+            }
+
+            // 提示落点，只画外轮廓
+            if let Some(hint) = hint {
+                if let Some(hint_data) = game.board.piece_data.get(hint.typ) {
+                    let cells = &hint_data.orientation()[hint.orientation];
+                    let color = COLOR_TABLE[hint_data.color_id];
+                    let border = (cell_size / 8).max(1).min(cell_size);
+                    for (x_offset, y_offset) in cells {
+                        let x = start_x as isize + (hint.position.0 + *x_offset as isize) * cell_size as isize;
+                        let y = start_y as isize + (hint.position.1 + *y_offset as isize) * cell_size as isize;
+                        let has = |dx: isize, dy: isize| cells.iter().any(|(x1, y1)| *x1 as isize == *x_offset as isize + dx && *y1 as isize == *y_offset as isize + dy);
+                        if !has(-1, 0) {
+                            fill_rect_signed(buffer, x, y, border, cell_size, color);
+                        }
+                        if !has(1, 0) {
+                            fill_rect_signed(buffer, x + (cell_size - border) as isize, y, border, cell_size, color);
+                        }
+                        if !has(0, -1) {
+                            fill_rect_signed(buffer, x, y, cell_size, border, color);
+                        }
+                        if !has(0, 1) {
+                            fill_rect_signed(buffer, x, y + (cell_size - border) as isize, cell_size, border, color);
+                        }
+                    }
+                }
+            }
+
+            // 正常颜色
+            let x_index = current_piece.position.0;
+            let y_index = current_piece.position.1;
+            let color = COLOR_TABLE[piece_data.color_id];
+            for (x_offset, y_offset) in &piece_data.orientation()[current_piece.orientation] {
+                let x = start_x as isize + (x_index + *x_offset as isize) * cell_size as isize;
+                let y = start_y as isize + (y_index + *y_offset as isize) * cell_size as isize;
+                fill_rect_signed(buffer, x, y, cell_size, cell_size, color);
+            }
+        }
+    }
+
+    buffer.fill_rect(start_x + (game.board.board.width + 1) * cell_size, start_y + 2 * cell_size, 4 * cell_size, 15 * cell_size, Rgb::new(0, 0, 0));
+
+    let mut y_index = 2;
+    for typ in game.rng.preview() {
+        if let Some(piece_data) = game.board.piece_data.get(*typ) {
+            let x_index = game.board.board.width as isize + 1 + (4 - piece_data.initial_width as isize) / 2;
+            let color = COLOR_TABLE[piece_data.color_id];
+            for (x_offset, y_offset) in &piece_data.orientation()[piece_data.spawn_orientation] {
+                let x = start_x as isize + (x_index + *x_offset as isize) * cell_size as isize;
+                let y = start_y as isize + (y_index + *y_offset as isize) * cell_size as isize;
+                fill_rect_signed(buffer, x, y, cell_size, cell_size, color);
+            }
+            y_index += 3;
+        }
+    }
+
+    buffer.fill_rect(start_x - 5 * cell_size, start_y + 2 * cell_size, 4 * cell_size, 2 * cell_size, Rgb::new(0, 0, 0));
+
+    if let Some(typ) = game.hold {
+        if let Some(piece_data) = game.board.piece_data.get(typ) {
+            let x_index = -5;
+            let y_index = 2;
+            let color = COLOR_TABLE[piece_data.color_id];
+            for (x_offset, y_offset) in &piece_data.orientation()[piece_data.spawn_orientation] {
+                let x = start_x as isize + (x_index + *x_offset as isize) * cell_size as isize;
+                let y = start_y as isize + (y_index + *y_offset as isize) * cell_size as isize;
+                fill_rect_signed(buffer, x, y, cell_size, cell_size, color);
+            }
+        }
+    }
+
+    // finesse 错误计数，每个小方块表示一次错误。训练模式下为黄色
+    let pip_size = cell_size / 2;
+    buffer.fill_rect(start_x - 5 * cell_size, start_y + 5 * cell_size, 4 * cell_size, 5 * cell_size, Rgb::new(0, 0, 0));
+    let color = if game.config.finesse_training {
+        Rgb::new(227, 159, 2)
+    } else {
+        Rgb::new(215, 15, 55)
+    };
+    for i in 0..game.finesse_faults.min(80) {
+        let x = start_x - 5 * cell_size + (i % 8) * pip_size;
+        let y = start_y + 5 * cell_size + (i / 8) * pip_size;
+        buffer.fill_rect(x + 1, y + 1, pip_size.saturating_sub(2), pip_size.saturating_sub(2), color);
+    }
+
+    render_hud(game, buffer, (start_x, start_y), cell_size, seed);
+
+    buffer.fill_rect(start_x.saturating_sub(1), start_y + 2 * cell_size, 1, game.board.board.height.saturating_sub(2) * cell_size + 1, Rgb::new(255, 255, 255));
+    buffer.fill_rect(start_x.saturating_sub(1), start_y + 2 * cell_size, game.board.board.width * cell_size + 2, 1, Rgb::new(255, 255, 255));
+    buffer.fill_rect(start_x.saturating_sub(1), start_y + game.board.board.height * cell_size + 1, game.board.board.width * cell_size + 2, 1, Rgb::new(255, 255, 255));
+    buffer.fill_rect(start_x + game.board.board.width * cell_size + 1, start_y + 2 * cell_size, 1, game.board.board.height.saturating_sub(2) * cell_size + 1, Rgb::new(255, 255, 255));
+}
+
+/// 填充矩形，坐标可以是负数（例如版面上方的方块），超出缓冲区的部分忽略
+fn fill_rect_signed<F: Framebuffer>(buffer: &mut F, x: isize, y: isize, cx: usize, cy: usize, color: Rgb) {
+    let (x, cx) = (x.max(0) as usize, cx.saturating_sub(x.min(0).unsigned_abs()));
+    let (y, cy) = (y.max(0) as usize, cy.saturating_sub(y.min(0).unsigned_abs()));
+    buffer.fill_rect(x, y, cx, cy, color);
+}
+
+/// 绘制 HUD：HOLD、NEXT、FINESSE 标签，左侧的统计数据，以及版面下方的种子
+///
+/// 每行文字占一格，格子小于 GLYPH_HEIGHT + 2 像素时放不下，不绘制
+fn render_hud<F, G>(game: &Game<PreviewGenerator<usize, G>>, buffer: &mut F, (start_x, start_y): (usize, usize), cell_size: usize, seed: Option<u32>)
+    where
+        F: Framebuffer
+{
+    let scale = cell_size / (GLYPH_HEIGHT + 2);
+    if scale == 0 {
+        return;
+    }
+    let black = Rgb::new(0, 0, 0);
+    // 左右两侧各 5 格，左侧留出 1 像素给版面边框
+    let left = start_x - 5 * cell_size;
+    let right = start_x + (game.board.board.width + 1) * cell_size;
+    let panel_width = 5 * cell_size - 1;
+    let row_y = |row: usize| start_y + row * cell_size + (cell_size - GLYPH_HEIGHT * scale) / 2;
+
+    buffer.fill_rect(left, start_y + cell_size, panel_width, cell_size, black);
+    buffer.fill_rect(right, start_y + cell_size, panel_width, cell_size, black);
+    buffer.fill_rect(left, start_y + 4 * cell_size, panel_width, cell_size, black);
+    draw_text(buffer, (left, row_y(1)), scale, "HOLD", LABEL_COLOR);
+    draw_text(buffer, (right, row_y(1)), scale, "NEXT", LABEL_COLOR);
+    draw_text(buffer, (left, row_y(4)), scale, "FINESSE", LABEL_COLOR);
+
+    // 统计数据在 finesse 错误计数下方，标签和数值各占一行
+    let ms = game.current_tick as u64 * TICK_MS;
+    let stats = [
+        ("SCORE", format!("{}", game.score)),
+        ("LINES", format!("{}", game.lines)),
+        ("PIECES", format!("{}", game.pieces)),
+        ("TIME", format!("{}:{:02}.{}", ms / 60_000, ms / 1000 % 60, ms / 100 % 10)),
+    ];
+    buffer.fill_rect(left, start_y + 11 * cell_size, panel_width, 2 * stats.len() * cell_size, black);
+    for (i, (label, value)) in stats.iter().enumerate() {
+        draw_text(buffer, (left, row_y(11 + 2 * i)), scale, label, LABEL_COLOR);
+        draw_text(buffer, (left, row_y(12 + 2 * i)), scale, value, VALUE_COLOR);
+    }
+
+    if let Some(seed) = seed {
+        let text = format!("SEED {}", seed);
+        let board_width = game.board.board.width * cell_size;
+        let y = start_y + game.board.board.height * cell_size + cell_size / 2;
+        buffer.fill_rect(start_x, y, board_width, GLYPH_HEIGHT * scale, black);
+        let x = start_x + board_width.saturating_sub(text_width(&text, scale)) / 2;
+        draw_text(buffer, (x, y), scale, &text, VALUE_COLOR);
+    }
+}
@@ -0,0 +1,358 @@
+//! ## 录像
+//!
+//! 录像记录开局参数（随机数种子、方块组编号、版面大小、Config）和带 tick 序号的事件，回放时可以逐位复现整局游戏。
+//!
+//! 格式（codec 编码）：
+//!
+//! ```text
+//! "TRPL" 版本 种子 方块组 宽 高 初始行 预览数 Config（版本 3 起包含练习模式）
+//! 事件数 (与上一个事件的 tick 差, 事件编号)...
+//! 结束 tick 与最后一个事件的 tick 差
+//! 结束时的 Game::checksum（8 字节）
+//! 是否有声明的结果 [得分 行数 方块数]（版本 2 起）
+//! ```
+//!
+//! 游戏开始时先调用一次 Game::add_next_piece，之后的所有变化都来自 Game::on_event。
+
+use crate::codec::{Decode, Encode, Reader, Writer};
+use crate::{new_piece_data, Config, Event, ExtendedBoard, Game, PreviewGenerator, SevenBagGenerator, MAX_BOARD_HEIGHT, MAX_BOARD_WIDTH};
+use alloc::vec::Vec;
+
+/// 文件头
+pub const REPLAY_MAGIC: &[u8; 4] = b"TRPL";
+/// 当前的格式版本。版本 1 没有声明的结果，版本 2 的 Config 没有练习模式
+pub const REPLAY_VERSION: u8 = 3;
+/// 录像的最大预览数
+pub const MAX_PREVIEW_COUNT: usize = 64;
+/// 录像的最大 tick 数，15 毫秒一个 tick 时约 35 小时
+pub const MAX_REPLAY_TICKS: usize = 1 << 23;
+
+/// 录像使用的游戏类型
+pub type ReplayGame = Game<PreviewGenerator<usize, SevenBagGenerator>>;
+
+impl Event {
+    /// 事件编号，用于录像
+    pub fn id(&self) -> u8 {
+        match self {
+            Event::Tick => 0,
+            Event::RotateLeft => 1,
+            Event::RotateRight => 2,
+            Event::Rotate180 => 3,
+            Event::Hold => 4,
+            Event::HardDrop => 5,
+            Event::SoftDropFast => 6,
+            Event::Forfeit => 7,
+            Event::MoveLeftBegin => 8,
+            Event::MoveLeftEnd => 9,
+            Event::MoveRightBegin => 10,
+            Event::MoveRightEnd => 11,
+            Event::SoftDropBegin => 12,
+            Event::SoftDropEnd => 13,
+            Event::Undo => 14,
+            Event::Redo => 15,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        Some(match id {
+            0 => Event::Tick,
+            1 => Event::RotateLeft,
+            2 => Event::RotateRight,
+            3 => Event::Rotate180,
+            4 => Event::Hold,
+            5 => Event::HardDrop,
+            6 => Event::SoftDropFast,
+            7 => Event::Forfeit,
+            8 => Event::MoveLeftBegin,
+            9 => Event::MoveLeftEnd,
+            10 => Event::MoveRightBegin,
+            11 => Event::MoveRightEnd,
+            12 => Event::SoftDropBegin,
+            13 => Event::SoftDropEnd,
+            14 => Event::Undo,
+            15 => Event::Redo,
+            _ => return None,
+        })
+    }
+}
+
+/// 录像声明的结果
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReplayResult {
+    pub score: usize,
+    pub lines: usize,
+    pub pieces: usize,
+}
+
+impl ReplayResult {
+    pub fn from_game<G>(game: &Game<G>) -> Self {
+        Self {
+            score: game.score,
+            lines: game.lines,
+            pieces: game.pieces,
+        }
+    }
+}
+
+/// 录像
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    /// 7-Bag 的随机数种子
+    pub seed: u32,
+    /// 方块组编号，见 new_piece_data
+    pub piece_set: usize,
+    pub width: usize,
+    pub height: usize,
+    /// 初始行
+    pub start_y: isize,
+    /// 预览数
+    pub preview_count: usize,
+    pub config: Config,
+    /// (tick 序号, 事件)，见 Game::recording
+    pub events: Vec<(usize, Event)>,
+    /// 结束时的 tick 序号
+    pub end_tick: usize,
+    /// 结束时的 Game::checksum
+    pub checksum: u64,
+    /// 声明的结果，回放后应该一致
+    pub result: Option<ReplayResult>,
+}
+
+impl Replay {
+    /// 创建空录像，之后用 new_game 开始游戏，游戏结束后用 finish 保存结果
+    pub fn new(seed: u32, piece_set: usize, width: usize, height: usize, start_y: isize, preview_count: usize, config: Config) -> Self {
+        Self {
+            seed,
+            piece_set,
+            width,
+            height,
+            start_y,
+            preview_count,
+            config,
+            events: Vec::new(),
+            end_tick: 0,
+            checksum: 0,
+            result: None,
+        }
+    }
+
+    /// 版面大小、初始行、预览数和结束 tick 是否都在允许的范围内
+    pub fn is_valid(&self) -> bool {
+        (1..=MAX_BOARD_WIDTH).contains(&self.width)
+            && (1..=MAX_BOARD_HEIGHT).contains(&self.height)
+            && self.start_y.unsigned_abs() <= MAX_BOARD_HEIGHT
+            && self.preview_count <= MAX_PREVIEW_COUNT
+            && self.end_tick <= MAX_REPLAY_TICKS
+    }
+
+    /// 按录像的参数创建游戏，并放入第一个方块
+    ///
+    /// * `record`: 是否开启录制
+    ///
+    /// returns: Option<ReplayGame> 参数超出范围（见 is_valid）或者方块组编号未知时返回 None
+    pub fn new_game(&self, record: bool) -> Option<ReplayGame> {
+        if !self.is_valid() {
+            return None;
+        }
+        let piece_data = new_piece_data(self.piece_set)?;
+        let types = piece_data.len();
+        let board = ExtendedBoard::new(self.width, self.height, piece_data, self.start_y);
+        let mut game = Game::new(self.config, board, PreviewGenerator::new(SevenBagGenerator::new(self.seed, types), self.preview_count));
+        if record {
+            game.recording = Some(Vec::new());
+        }
+        game.enable_undo();
+        // 第一个方块就放不下时游戏直接结束，回放结果同样如此
+        let _ = game.add_next_piece();
+        Some(game)
+    }
+
+    /// 从录制中的游戏保存事件和结束状态
+    pub fn finish<G>(&mut self, game: &Game<G>) {
+        self.events = game.recording.clone().unwrap_or_default();
+        self.end_tick = game.current_tick;
+        self.checksum = game.checksum();
+        self.result = Some(ReplayResult::from_game(game));
+    }
+
+    /// 创建回放器
+    pub fn player(&self) -> ReplayPlayer<'_> {
+        ReplayPlayer {
+            replay: self,
+            index: 0,
+            finished: false,
+        }
+    }
+
+    /// 回放整局游戏
+    pub fn play(&self) -> Option<ReplayGame> {
+        let mut game = self.new_game(false)?;
+        let mut player = self.player();
+        while player.step(&mut game) {}
+        Some(game)
+    }
+
+    /// 回放整局游戏，检查结束状态和声明的结果是否与录制时一致
+    pub fn verify(&self) -> bool {
+        match self.play() {
+            Some(game) => self.matches(&game) && self.result.is_none_or(|result| result == ReplayResult::from_game(&game)),
+            None => false,
+        }
+    }
+
+    /// 回放结束的游戏状态是否与录制时一致（不检查声明的结果）
+    pub fn matches<G>(&self, game: &Game<G>) -> bool {
+        game.current_tick == self.end_tick && game.checksum() == self.checksum
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        writer.write_bytes(REPLAY_MAGIC);
+        writer.write_u8(REPLAY_VERSION);
+        writer.write_varint(self.seed as u64);
+        writer.write_usize(self.piece_set);
+        writer.write_usize(self.width);
+        writer.write_usize(self.height);
+        writer.write_isize(self.start_y);
+        writer.write_usize(self.preview_count);
+        writer.write(&self.config);
+        writer.write_usize(self.events.len());
+        let mut last_tick = 0;
+        for (tick, event) in &self.events {
+            writer.write_usize(tick - last_tick);
+            writer.write_u8(event.id());
+            last_tick = *tick;
+        }
+        writer.write_usize(self.end_tick - last_tick);
+        writer.write_u64(self.checksum);
+        writer.write_bool(self.result.is_some());
+        if let Some(result) = &self.result {
+            writer.write_usize(result.score);
+            writer.write_usize(result.lines);
+            writer.write_usize(result.pieces);
+        }
+        writer.into_bytes()
+    }
+
+    /// 解码录像，格式错误、版本不支持或者参数超出范围时返回 None
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let mut reader = Reader::new(data);
+        if reader.read_bytes(REPLAY_MAGIC.len())? != REPLAY_MAGIC {
+            return None;
+        }
+        let version = reader.read_u8()?;
+        if version == 0 || version > REPLAY_VERSION {
+            return None;
+        }
+        let mut replay = Replay::new(
+            u32::try_from(reader.read_varint()?).ok()?,
+            reader.read_usize()?,
+            reader.read_usize()?,
+            reader.read_usize()?,
+            reader.read_isize()?,
+            reader.read_usize()?,
+            if version >= 3 {
+                reader.read()?
+            } else {
+                read_config_v2(&mut reader)?
+            },
+        );
+        // 先检查头部，结束 tick 解码事件之后再检查
+        if !replay.is_valid() {
+            return None;
+        }
+        let count = reader.read_usize()?;
+        let mut tick = 0usize;
+        for _ in 0..count {
+            tick = tick.checked_add(reader.read_usize()?)?;
+            replay.events.push((tick, Event::from_id(reader.read_u8()?)?));
+        }
+        replay.end_tick = tick.checked_add(reader.read_usize()?)?;
+        if replay.end_tick > MAX_REPLAY_TICKS {
+            return None;
+        }
+        replay.checksum = reader.read_u64()?;
+        if version >= 2 && reader.read_bool()? {
+            replay.result = Some(ReplayResult {
+                score: reader.read_usize()?,
+                lines: reader.read_usize()?,
+                pieces: reader.read_usize()?,
+            });
+        }
+        if !reader.is_empty() {
+            return None;
+        }
+        Some(replay)
+    }
+}
+
+/// 版本 2 及之前的 Config：没有练习模式
+fn read_config_v2(reader: &mut Reader) -> Option<Config> {
+    Some(Config {
+        das: reader.read_f32()?,
+        arr: reader.read_f32()?,
+        sdf: reader.read_f32()?,
+        gravity: reader.read_f32()?,
+        lock_delay: reader.read_usize()?,
+        max_reset_times: reader.read_usize()?,
+        finesse_das: reader.read_bool()?,
+        finesse_training: reader.read_bool()?,
+        practice: false,
+    })
+}
+
+/// 存档中的录像：保存整个录像文件的数据，沿用录像自己的版本号
+impl Encode for Replay {
+    fn write_to(&self, writer: &mut Writer) {
+        writer.write(&self.encode());
+    }
+}
+
+impl Decode for Replay {
+    fn read_from(reader: &mut Reader) -> Option<Self> {
+        let data: Vec<u8> = reader.read()?;
+        Replay::decode(&data)
+    }
+}
+
+/// 逐帧回放
+pub struct ReplayPlayer<'a> {
+    replay: &'a Replay,
+    /// 下一个要发送的事件
+    index: usize,
+    finished: bool,
+}
+
+impl ReplayPlayer<'_> {
+    /// 推进一帧：发送这一帧录制的事件，然后 Tick
+    ///
+    /// returns: bool 录像是否还没有结束
+    pub fn step<G>(&mut self, game: &mut Game<G>) -> bool
+        where
+            G: Iterator<Item=usize>
+    {
+        if self.finished {
+            return false;
+        }
+        while let Some((tick, event)) = self.replay.events.get(self.index) {
+            if *tick != game.current_tick {
+                break;
+            }
+            self.index += 1;
+            if game.on_event(*event).is_err() {
+                self.finished = true;
+                return false;
+            }
+        }
+        if game.current_tick >= self.replay.end_tick || game.on_event(Event::Tick).is_err() {
+            self.finished = true;
+            return false;
+        }
+        true
+    }
+
+    /// 回放进度：(当前 tick, 结束 tick)
+    pub fn progress<G>(&self, game: &Game<G>) -> (usize, usize) {
+        (game.current_tick, self.replay.end_tick)
+    }
+}
@@ -1,7 +1,18 @@
+use crate::codec::{Decode, Encode, Reader, Writer};
+use crate::MAX_PREVIEW_COUNT;
 use alloc::collections::vec_deque::Iter;
 use alloc::collections::VecDeque;
 use alloc::vec::Vec;
 
+/// 7-Bag 生成器最多的方块种类，用于拒绝存档中异常的 type_count
+pub const MAX_TYPE_COUNT: usize = 64;
+
+/// 只生成有限种方块的生成器，读取存档时用来检查生成器与方块组一致
+pub trait PieceTypes {
+    /// 生成器是否正好生成 0..type_count 的方块
+    fn has_type_count(&self, type_count: usize) -> bool;
+}
+
 /// 标准伪随机数算法
 pub fn prng(state: &mut u32) -> u32 {
     let new_state = state.wrapping_mul(1103515245).wrapping_add(12345);
@@ -19,6 +30,7 @@ pub fn shuffle<T>(list: &mut [T], state: &mut u32) {
 }
 
 /// 7-Bag 生成器
+#[derive(Clone)]
 pub struct SevenBagGenerator {
     state: u32,
     type_count: usize,
@@ -39,7 +51,7 @@ impl Iterator for SevenBagGenerator {
     type Item = usize;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.queue.len() == 0 {
+        if self.queue.is_empty() {
             self.queue.extend(0..self.type_count);
             shuffle(self.queue.as_mut_slice(), &mut self.state);
         }
@@ -48,6 +60,7 @@ impl Iterator for SevenBagGenerator {
 }
 
 /// 带预览功能的生成器
+#[derive(Clone)]
 pub struct PreviewGenerator<T, G> {
     rng: G,
     preview_count: usize,
@@ -94,3 +107,69 @@ impl<T, G> Iterator for PreviewGenerator<T, G>
         }
     }
 }
+
+impl PieceTypes for SevenBagGenerator {
+    fn has_type_count(&self, type_count: usize) -> bool {
+        self.type_count == type_count
+    }
+}
+
+impl<G: PieceTypes> PieceTypes for PreviewGenerator<usize, G> {
+    fn has_type_count(&self, type_count: usize) -> bool {
+        self.rng.has_type_count(type_count) && self.preview_list.iter().all(|typ| *typ < type_count)
+    }
+}
+
+impl Encode for SevenBagGenerator {
+    fn write_to(&self, writer: &mut Writer) {
+        writer.write(&self.state);
+        writer.write_usize(self.type_count);
+        writer.write(&self.queue);
+    }
+}
+
+impl Decode for SevenBagGenerator {
+    fn read_from(reader: &mut Reader) -> Option<Self> {
+        let state = reader.read()?;
+        let type_count = reader.read_usize()?;
+        let queue: Vec<usize> = reader.read()?;
+        if !(1..=MAX_TYPE_COUNT).contains(&type_count) || queue.len() > type_count || queue.iter().any(|typ| *typ >= type_count) {
+            return None;
+        }
+        Some(Self {
+            state,
+            type_count,
+            queue,
+        })
+    }
+}
+
+impl<T: Encode, G: Encode> Encode for PreviewGenerator<T, G> {
+    fn write_to(&self, writer: &mut Writer) {
+        writer.write(&self.rng);
+        writer.write_usize(self.preview_count);
+        writer.write_usize(self.preview_list.len());
+        for v in &self.preview_list {
+            writer.write(v);
+        }
+    }
+}
+
+impl<T: Decode, G: Decode> Decode for PreviewGenerator<T, G> {
+    fn read_from(reader: &mut Reader) -> Option<Self> {
+        let rng = reader.read()?;
+        let preview_count = reader.read_usize()?;
+        if preview_count > MAX_PREVIEW_COUNT {
+            return None;
+        }
+        let preview_list: Vec<T> = reader.read()?;
+        if preview_list.len() > preview_count {
+            return None;
+        }
+        Some(Self {
+            rng,
+            preview_count,
+            preview_list: preview_list.into(),
+        })
+    }
+}
@@ -0,0 +1,291 @@
+//! ## 存档
+//!
+//! 将正在进行的游戏（Game、ExtendedBoard、Board、Piece 和生成器）编码为带版本号的二进制数据，下次启动时可以从存档继续。
+//!
+//! 格式（codec 编码）：
+//!
+//! ```text
+//! "TSAV" 版本 数据
+//! ```
+//!
+//! 数据是任意实现了 Encode 的值，例如 (ReplayGame, Replay)。方块数据表整个写入存档，不依赖方块组编号。
+//! Board 的标记行只在消行动画期间存在，不写入存档。练习模式的撤销记录也不写入存档，继续游戏后从当前方块重新开始记录。
+
+use crate::codec::{Decode, Encode, Reader, Writer};
+use crate::{Board, Config, Event, ExtendedBoard, Game, Piece, PieceData, PieceTypes, COLOR_TABLE, MAX_BOARD_HEIGHT, MAX_BOARD_WIDTH};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// 文件头
+pub const SAVE_MAGIC: &[u8; 4] = b"TSAV";
+/// 当前的格式版本。版本 1 的 Config 没有练习模式，读取时关闭练习模式；
+/// 版本 2 以前的 PieceData 没有 t_spin，读取时按名称判定 T 方块
+pub const SAVE_VERSION: u8 = 3;
+
+/// 编码存档
+pub fn encode_save<T: Encode + ?Sized>(value: &T) -> Vec<u8> {
+    let mut writer = Writer::new();
+    writer.write_bytes(SAVE_MAGIC);
+    writer.write_u8(SAVE_VERSION);
+    writer.write(value);
+    writer.into_bytes()
+}
+
+/// 解码存档，格式错误或者版本不支持时返回 None
+pub fn decode_save<T: Decode>(data: &[u8]) -> Option<T> {
+    let mut reader = Reader::new(data);
+    if reader.read_bytes(SAVE_MAGIC.len())? != SAVE_MAGIC {
+        return None;
+    }
+    let version = reader.read_u8()?;
+    if version == 0 || version > SAVE_VERSION {
+        return None;
+    }
+    let mut reader = Reader::with_version(&data[SAVE_MAGIC.len() + 1..], version);
+    let value = reader.read()?;
+    if !reader.is_empty() {
+        return None;
+    }
+    Some(value)
+}
+
+impl Encode for Config {
+    fn write_to(&self, writer: &mut Writer) {
+        writer.write_f32(self.das);
+        writer.write_f32(self.arr);
+        writer.write_f32(self.sdf);
+        writer.write_f32(self.gravity);
+        writer.write_usize(self.lock_delay);
+        writer.write_usize(self.max_reset_times);
+        writer.write_bool(self.finesse_das);
+        writer.write_bool(self.finesse_training);
+        writer.write_bool(self.practice);
+    }
+}
+
+impl Decode for Config {
+    fn read_from(reader: &mut Reader) -> Option<Self> {
+        Some(Config {
+            das: reader.read_f32()?,
+            arr: reader.read_f32()?,
+            sdf: reader.read_f32()?,
+            gravity: reader.read_f32()?,
+            lock_delay: reader.read_usize()?,
+            max_reset_times: reader.read_usize()?,
+            finesse_das: reader.read_bool()?,
+            finesse_training: reader.read_bool()?,
+            // 版本 1 的存档没有这一项
+            practice: reader.version() != 1 && reader.read_bool()?,
+        })
+    }
+}
+
+impl Encode for Event {
+    fn write_to(&self, writer: &mut Writer) {
+        writer.write_u8(self.id());
+    }
+}
+
+impl Decode for Event {
+    fn read_from(reader: &mut Reader) -> Option<Self> {
+        Event::from_id(reader.read_u8()?)
+    }
+}
+
+impl Encode for PieceData {
+    fn write_to(&self, writer: &mut Writer) {
+        writer.write(&self.name);
+        writer.write_usize(self.initial_width);
+        writer.write(&self.orientation);
+        writer.write(&self.test_table);
+        writer.write_usize(self.spawn_orientation);
+        writer.write(&self.spawn_offset);
+        writer.write_usize(self.color_id);
+        writer.write_bool(self.t_spin);
+    }
+}
+
+impl Decode for PieceData {
+    fn read_from(reader: &mut Reader) -> Option<Self> {
+        let name: String = reader.read()?;
+        let initial_width = reader.read_usize()?;
+        let orientation: Vec<Vec<(usize, usize)>> = reader.read()?;
+        let test_table: Vec<Vec<Vec<(isize, isize)>>> = reader.read()?;
+        // PieceMask 要求小块的偏移能放进一个 u64
+        if orientation.is_empty()
+            || test_table.len() != orientation.len()
+            || orientation.iter().flatten().any(|(x, y)| *x >= MAX_BOARD_WIDTH || *y >= MAX_BOARD_WIDTH) {
+            return None;
+        }
+        let spawn_orientation = reader.read_usize()?;
+        if spawn_orientation >= orientation.len() {
+            return None;
+        }
+        let spawn_offset = reader.read()?;
+        let color_id = reader.read_usize()?;
+        if color_id >= COLOR_TABLE.len() {
+            return None;
+        }
+        Some(PieceData {
+            spawn_orientation,
+            spawn_offset,
+            color_id,
+            t_spin: match reader.version() {
+                1 | 2 => name == "T",
+                _ => reader.read_bool()?,
+            },
+            ..PieceData::new(name, initial_width, orientation, test_table)
+        })
+    }
+}
+
+impl Encode for Board {
+    fn write_to(&self, writer: &mut Writer) {
+        writer.write_usize(self.width);
+        writer.write_usize(self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                // 0 表示空，否则是颜色编号加 1
+                writer.write_usize(match self.get_cell(x, y) {
+                    Some(Some(color_id)) => color_id + 1,
+                    _ => 0,
+                });
+            }
+        }
+    }
+}
+
+impl Decode for Board {
+    fn read_from(reader: &mut Reader) -> Option<Self> {
+        let width = reader.read_usize()?;
+        let height = reader.read_usize()?;
+        // 与录像相同的大小限制；每个小块至少占一个字节，先检查长度再分配版面
+        if !(1..=MAX_BOARD_WIDTH).contains(&width) || !(1..=MAX_BOARD_HEIGHT).contains(&height) || width * height > reader.remaining() {
+            return None;
+        }
+        let mut board = Board::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                // 颜色编号要能查颜色表
+                let cell = reader.read_usize()?;
+                if cell > COLOR_TABLE.len() {
+                    return None;
+                }
+                board.set_cell(x, y, cell.checked_sub(1));
+            }
+        }
+        Some(board)
+    }
+}
+
+impl Encode for Piece {
+    fn write_to(&self, writer: &mut Writer) {
+        writer.write_usize(self.typ);
+        writer.write(&self.position);
+        writer.write_usize(self.orientation);
+    }
+}
+
+impl Decode for Piece {
+    fn read_from(reader: &mut Reader) -> Option<Self> {
+        Some(Piece {
+            typ: reader.read_usize()?,
+            position: reader.read()?,
+            orientation: reader.read_usize()?,
+        })
+    }
+}
+
+impl Encode for ExtendedBoard {
+    fn write_to(&self, writer: &mut Writer) {
+        writer.write(&self.piece_data);
+        writer.write(&self.board);
+        writer.write(&self.current_piece);
+        writer.write_isize(self.start_y);
+    }
+}
+
+impl Decode for ExtendedBoard {
+    fn read_from(reader: &mut Reader) -> Option<Self> {
+        let piece_data: Vec<PieceData> = reader.read()?;
+        let board = reader.read()?;
+        let current_piece: Option<Piece> = reader.read()?;
+        if let Some(piece) = &current_piece {
+            if piece_data.get(piece.typ).is_none_or(|data| piece.orientation >= data.orientation().len()) {
+                return None;
+            }
+        }
+        Some(ExtendedBoard {
+            piece_data,
+            board,
+            current_piece,
+            start_y: reader.read_isize()?,
+        })
+    }
+}
+
+impl<G: Encode> Encode for Game<G> {
+    fn write_to(&self, writer: &mut Writer) {
+        writer.write(&self.config);
+        writer.write(&self.board);
+        writer.write(&self.rng);
+        writer.write(&self.hold);
+        writer.write_bool(self.hold_used);
+        writer.write_usize(self.current_tick);
+        writer.write_f32(self.gravity_last_tick);
+        writer.write_bool(self.move_left_down);
+        writer.write_bool(self.move_right_down);
+        writer.write_isize(self.move_direction);
+        writer.write_f32(self.move_last_tick);
+        writer.write_bool(self.move_auto_repeat);
+        writer.write_bool(self.soft_drop_down);
+        writer.write_f32(self.soft_drop_last_tick);
+        writer.write_usize(self.land_tick_count);
+        writer.write_usize(self.reset_times);
+        writer.write_usize(self.piece_inputs);
+        writer.write_usize(self.last_finesse_faults);
+        writer.write_usize(self.finesse_faults);
+        writer.write_usize(self.pieces);
+        writer.write_usize(self.lines);
+        writer.write_usize(self.score);
+        writer.write_usize(self.combo);
+        writer.write_bool(self.back_to_back);
+        writer.write_bool(self.last_move_rotation);
+        writer.write(&self.recording);
+    }
+}
+
+impl<G: Decode + PieceTypes + Iterator<Item=usize> + Clone> Decode for Game<G> {
+    fn read_from(reader: &mut Reader) -> Option<Self> {
+        let mut game: Game<G> = Game::new(reader.read()?, reader.read()?, reader.read()?);
+        game.hold = reader.read()?;
+        game.hold_used = reader.read_bool()?;
+        game.current_tick = reader.read_usize()?;
+        game.gravity_last_tick = reader.read_f32()?;
+        game.move_left_down = reader.read_bool()?;
+        game.move_right_down = reader.read_bool()?;
+        game.move_direction = reader.read_isize()?;
+        game.move_last_tick = reader.read_f32()?;
+        game.move_auto_repeat = reader.read_bool()?;
+        game.soft_drop_down = reader.read_bool()?;
+        game.soft_drop_last_tick = reader.read_f32()?;
+        game.land_tick_count = reader.read_usize()?;
+        game.reset_times = reader.read_usize()?;
+        game.piece_inputs = reader.read_usize()?;
+        game.last_finesse_faults = reader.read_usize()?;
+        game.finesse_faults = reader.read_usize()?;
+        game.pieces = reader.read_usize()?;
+        game.lines = reader.read_usize()?;
+        game.score = reader.read_usize()?;
+        game.combo = reader.read_usize()?;
+        game.back_to_back = reader.read_bool()?;
+        game.last_move_rotation = reader.read_bool()?;
+        game.recording = reader.read()?;
+        let type_count = game.board.piece_data.len();
+        if game.hold.is_some_and(|hold| hold >= type_count) || !game.rng.has_type_count(type_count) {
+            return None;
+        }
+        game.enable_undo();
+        Some(game)
+    }
+}
@@ -0,0 +1,88 @@
+//! ## 版面文本格式
+//!
+//! 每行一个字符串，从上到下排列。`.` 表示空格子，`IJLOSTZ` 表示对应颜色的方块，`G` 表示垃圾行。
+//!
+//! ```text
+//! ..........
+//! ....T.....
+//! GGGTTT.GGG
+//! ```
+
+use crate::{Board, GARBAGE_COLOR_ID, MAX_BOARD_HEIGHT, MAX_BOARD_WIDTH};
+use alloc::string::String;
+
+/// 文本格式中使用的颜色字符，下标为默认方块数据的 color_id
+pub const COLOR_CHARS: &[u8] = b"IJLOSTZG";
+
+/// 颜色编号转为文本字符。超出默认颜色表的编号视为垃圾行
+pub fn color_id_to_char(color_id: usize) -> char {
+    COLOR_CHARS.get(color_id).copied().unwrap_or(COLOR_CHARS[GARBAGE_COLOR_ID]) as char
+}
+
+/// 文本字符转为颜色编号
+pub fn char_to_color_id(c: char) -> Option<usize> {
+    COLOR_CHARS.iter().position(|v| *v as char == c)
+}
+
+impl Board {
+    /// 转为文本格式，每行以 `\n` 结尾
+    pub fn to_text(&self) -> String {
+        let mut text = String::with_capacity((self.width + 1) * self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if let Some(Some(color_id)) = self.get_cell(x, y) {
+                    text.push(color_id_to_char(*color_id));
+                } else {
+                    text.push('.');
+                }
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    /// 从文本格式创建版面，宽度和高度由文本决定。忽略空行，每行长度必须相同
+    pub fn from_text(text: &str) -> Option<Self> {
+        let lines = text.lines().map(|line| line.trim()).filter(|line| !line.is_empty());
+        let width = lines.clone().next()?.chars().count();
+        let height = lines.clone().count();
+        if width > MAX_BOARD_WIDTH || height > MAX_BOARD_HEIGHT || lines.clone().any(|line| line.chars().count() != width) {
+            return None;
+        }
+        let mut board = Self::new(width, height);
+        if board.load_text(text) {
+            Some(board)
+        } else {
+            None
+        }
+    }
+
+    /// 将文本格式载入到当前版面，文本与版面左下角对齐，其余部分清空
+    ///
+    /// returns: bool 文本是否有效且能放入版面
+    pub fn load_text(&mut self, text: &str) -> bool {
+        let lines = text.lines().map(|line| line.trim()).filter(|line| !line.is_empty());
+        let height = lines.clone().count();
+        if height > self.height {
+            return false;
+        }
+        let mut board = Self::new(self.width, self.height);
+        for (i, line) in lines.enumerate() {
+            let y = self.height - height + i;
+            for (x, c) in line.chars().enumerate() {
+                if x >= self.width {
+                    return false;
+                }
+                if c != '.' {
+                    if let Some(color_id) = char_to_color_id(c) {
+                        board.set_cell(x, y, Some(color_id));
+                    } else {
+                        return false;
+                    }
+                }
+            }
+        }
+        *self = board;
+        true
+    }
+}
@@ -0,0 +1,60 @@
+//! 版面分析测试，期望值按定义手工计算
+
+use tetris::Board;
+
+#[test]
+fn analyze_small_board() {
+    // 列高 2 4 1 2 3，第 1 列最下面有一个空洞，上方有 3 个已填充小块
+    let board = Board::from_text("
+        .....
+        .G...
+        .G..G
+        GG.GG
+        G.GGG
+    ").unwrap();
+    let analysis = board.analyze();
+    assert_eq!(analysis.width, 5);
+    assert_eq!(analysis.column_heights[..5], [2, 4, 1, 2, 3]);
+    assert_eq!(analysis.max_height, 4);
+    assert_eq!(analysis.aggregate_height, 12);
+    assert_eq!(analysis.holes, 1);
+    assert_eq!(analysis.covered_cells, 3);
+    assert_eq!(analysis.bumpiness, 2 + 3 + 1 + 1);
+    // 墙视为与版面等高：第 0 列 min(5, 4) - 2，第 2 列 min(4, 2) - 1
+    assert_eq!(analysis.well_depths[..5], [2, 0, 1, 0, 0]);
+    assert_eq!(analysis.well_depth_sum, 3);
+    assert_eq!(analysis.deepest_well, (0, 2));
+    // 每行（两侧为墙）：2 + 4 + 4 + 2 + 2
+    assert_eq!(analysis.row_transitions, 14);
+    // 每列（底部为已填充）：1 + 3 + 1 + 1 + 1
+    assert_eq!(analysis.column_transitions, 7);
+}
+
+#[test]
+fn analyze_counts_every_hole_but_covers_only_the_lowest() {
+    // 第 0 列有两个空洞，最低的空洞上方有 2 个已填充小块；第 1 列是空列，形成深度 2 的井
+    let board = Board::from_text("
+        G..
+        ...
+        G.G
+        ..G
+    ").unwrap();
+    let analysis = board.analyze();
+    assert_eq!(analysis.column_heights[..3], [4, 0, 2]);
+    assert_eq!(analysis.holes, 2);
+    assert_eq!(analysis.covered_cells, 2);
+    assert_eq!(analysis.well_depths[..3], [0, 2, 0]);
+    assert_eq!(analysis.deepest_well, (1, 2));
+}
+
+#[test]
+fn analyze_empty_board() {
+    let analysis = Board::new(10, 20).analyze();
+    assert_eq!(analysis.column_heights[..10], [0; 10]);
+    assert_eq!((analysis.max_height, analysis.aggregate_height, analysis.holes, analysis.bumpiness), (0, 0, 0, 0));
+    assert_eq!(analysis.well_depth_sum, 0);
+    assert_eq!(analysis.deepest_well, (0, 0));
+    // 每行只有两侧墙边的两次变化，每列只有最下面一格与底部的变化
+    assert_eq!(analysis.row_transitions, 2 * 20);
+    assert_eq!(analysis.column_transitions, 10);
+}
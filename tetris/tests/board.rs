@@ -0,0 +1,134 @@
+//! 位掩码碰撞检测与逐格检测的对比测试，以及消行
+
+use tetris::{char_to_color_id, new_default_piece_data, prng, Board, PieceData, GARBAGE_COLOR_ID, MAX_BOARD_WIDTH};
+
+/// 逐格检测：每个小块都在版面内且所在格子为空
+fn test_piece_by_cells(board: &Board, piece_data: &PieceData, (x, y): (isize, isize), orientation: usize) -> bool {
+    let cells = match piece_data.orientation().get(orientation) {
+        Some(cells) => cells,
+        None => return false,
+    };
+    cells.iter().all(|(dx, dy)| {
+        let (cell_x, cell_y) = (x + *dx as isize, y + *dy as isize);
+        cell_x >= 0 && cell_y >= 0 && board.get_cell(cell_x as usize, cell_y as usize) == Some(&None)
+    })
+}
+
+/// 默认方块加上几个自定义方块：单格、五连的十字、横跨 8 列的长条
+fn piece_set() -> Vec<PieceData> {
+    let mut pieces = new_default_piece_data();
+    pieces.push(PieceData::new("1".into(), 1, vec![vec![(0, 0)]], vec![vec![vec![(0, 0)]; 4]]));
+    let plus = vec![(1, 0), (0, 1), (1, 1), (2, 1), (1, 2)];
+    pieces.push(PieceData::new("+".into(), 3, vec![plus.clone(), plus.clone(), plus.clone(), plus], vec![vec![vec![(0, 0)]; 4]; 4]));
+    let long: Vec<(usize, usize)> = (0..8).map(|x| (x, 1)).collect();
+    let tall: Vec<(usize, usize)> = (0..8).map(|y| (3, y)).collect();
+    pieces.push(PieceData::new("8".into(), 8, vec![long, tall], vec![vec![vec![(0, 0)]; 4]; 2]));
+    pieces
+}
+
+#[test]
+fn bitboard_collision_matches_cell_check() {
+    let pieces = piece_set();
+    let mut state = 1;
+    for _ in 0..300 {
+        let width = 1 + prng(&mut state) as usize % MAX_BOARD_WIDTH;
+        let height = 1 + prng(&mut state) as usize % 30;
+        let density = prng(&mut state) % 100;
+        let mut board = Board::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                if prng(&mut state) % 100 < density {
+                    board.set_cell(x, y, Some(0));
+                }
+            }
+        }
+        for piece_data in &pieces {
+            // 越界的朝向编号也要一致地返回 false
+            for orientation in 0..piece_data.orientation().len() + 1 {
+                for y in -9..height as isize + 2 {
+                    for x in -9..width as isize + 2 {
+                        assert_eq!(
+                            board.test_piece(piece_data, (x, y), orientation),
+                            test_piece_by_cells(&board, piece_data, (x, y), orientation),
+                            "piece {} orientation {} at ({}, {}) on {}x{} board",
+                            piece_data.name, orientation, x, y, width, height,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn text(board: &Board) -> String {
+    board.to_text().split_whitespace().collect::<Vec<_>>().join("\n")
+}
+
+/// 一整行相同颜色
+fn row(c: char, width: usize) -> Vec<Option<usize>> {
+    vec![char_to_color_id(c); width]
+}
+
+#[test]
+fn clear_a_single_row() {
+    let mut board = Board::from_text("
+        ....
+        I...
+        IIII
+        .T..
+    ").unwrap();
+    let cleared = board.clear_filled_rows();
+    assert_eq!(cleared.indices, [2]);
+    assert_eq!(cleared.row(0), row('I', 4));
+    assert_eq!(text(&board), "....\n....\nI...\n.T..");
+}
+
+#[test]
+fn mark_then_collapse_rows_that_are_not_adjacent() {
+    let mut board = Board::from_text("
+        .S..
+        ZZZZ
+        L...
+        GGGG
+        ..O.
+    ").unwrap();
+    let before = text(&board);
+    let marked = board.mark_filled_rows();
+    assert_eq!(marked.indices, [1, 3]);
+    assert_eq!(marked.len(), 2);
+    assert_eq!(marked.row(0), row('Z', 4));
+    // 垃圾行也按颜色保存
+    assert_eq!(marked.row(1), [Some(GARBAGE_COLOR_ID); 4]);
+    let rows: Vec<usize> = marked.iter().map(|(y, _)| y).collect();
+    assert_eq!(rows, [1, 3]);
+    // 标记不改变版面
+    assert_eq!(text(&board), before);
+
+    let collapsed = board.collapse_marked_rows();
+    assert_eq!(collapsed.indices, [1, 3]);
+    assert_eq!(collapsed.row(1), [Some(GARBAGE_COLOR_ID); 4]);
+    assert_eq!(text(&board), "....\n....\n.S..\nL...\n..O.");
+    // 消除之后仍然可以获取，直到下一次标记
+    assert_eq!(board.marked_rows().indices, [1, 3]);
+    assert!(board.mark_filled_rows().is_empty());
+}
+
+#[test]
+fn collapse_without_marked_rows_changes_nothing() {
+    let mut board = Board::from_text("
+        .J..
+        JJJJ
+    ").unwrap();
+    let before = text(&board);
+    // 没有标记过的满行不会被消除
+    assert!(board.collapse_marked_rows().is_empty());
+    assert_eq!(text(&board), before);
+
+    let mut board = Board::from_text("
+        .J..
+        JJ.J
+    ").unwrap();
+    assert!(board.mark_filled_rows().is_empty());
+    assert!(board.collapse_marked_rows().is_empty());
+    assert_eq!(text(&board), ".J..\nJJ.J");
+}
@@ -0,0 +1,94 @@
+//! finesse 统计和训练模式测试
+
+use tetris::{new_default_piece_data, Config, Event, ExtendedBoard, Game};
+
+const I: usize = 0;
+const O: usize = 3;
+const S: usize = 4;
+const T: usize = 5;
+
+const TAP_LEFT: &[Event] = &[Event::MoveLeftBegin, Event::MoveLeftEnd];
+
+type TestGame = Game<std::iter::Copied<std::slice::Iter<'static, usize>>>;
+
+/// 没有重力的空版面，按顺序出现指定的方块
+fn new_game(config: Config, pieces: &'static [usize]) -> TestGame {
+    let board = ExtendedBoard::new(10, 22, new_default_piece_data(), 0);
+    let mut game = Game::new(Config { gravity: 0.0, ..config }, board, pieces.iter().copied());
+    game.count_finesse = true;
+    game.add_next_piece().unwrap();
+    game
+}
+
+/// 发送事件然后硬降，返回这个方块的 finesse 错误数
+fn place(game: &mut TestGame, events: &[&[Event]]) -> usize {
+    for event in events.concat() {
+        game.on_event(event).unwrap();
+    }
+    let pieces = game.pieces;
+    game.on_event(Event::HardDrop).unwrap();
+    assert_eq!(game.pieces, pieces + 1);
+    game.last_finesse_faults
+}
+
+#[test]
+fn optimal_placements_count_no_faults() {
+    let mut game = new_game(Config::default(), &[T, O, S, I, I]);
+    assert_eq!(place(&mut game, &[]), 0);
+    assert_eq!(place(&mut game, &[TAP_LEFT]), 0);
+    assert_eq!(place(&mut game, &[&[Event::RotateRight]]), 0);
+    assert_eq!(game.finesse_faults, 0);
+}
+
+#[test]
+fn one_extra_input_counts_one_fault() {
+    let mut game = new_game(Config::default(), &[T, O, O, S, I, I]);
+    // 两次右旋代替一次 180 度旋转
+    assert_eq!(place(&mut game, &[&[Event::RotateRight, Event::RotateRight]]), 1);
+    // 左移三格：DAS 到墙再右移一格只要两次
+    assert_eq!(place(&mut game, &[TAP_LEFT, TAP_LEFT, TAP_LEFT]), 1);
+    // O 旋转之后占据相同的格子
+    assert_eq!(place(&mut game, &[&[Event::RotateRight]]), 1);
+    // 右旋再左移一格，与左旋占据相同的格子
+    assert_eq!(place(&mut game, &[&[Event::RotateRight], TAP_LEFT]), 1);
+    assert_eq!(game.finesse_faults, 4);
+}
+
+#[test]
+fn faults_are_not_counted_unless_requested() {
+    let mut game = new_game(Config::default(), &[O, I]);
+    game.count_finesse = false;
+    place(&mut game, &[&[Event::RotateRight]]);
+    assert_eq!((game.last_finesse_faults, game.finesse_faults), (0, 0));
+}
+
+#[test]
+fn training_respawns_the_same_piece_and_keeps_hold_used() {
+    let config = Config {
+        finesse_training: true,
+        ..Config::default()
+    };
+    let mut game = new_game(config, &[T, O, I, I]);
+    game.count_finesse = false;
+    game.on_event(Event::Hold).unwrap();
+    assert_eq!(game.hold, Some(T));
+    assert!(game.hold_used);
+
+    // 出现错误：不锁定，O 回到进场位置，仍然不能 Hold
+    game.on_event(Event::RotateRight).unwrap();
+    game.on_event(Event::HardDrop).unwrap();
+    assert_eq!(game.pieces, 0);
+    assert_eq!(game.last_finesse_faults, 1);
+    let current_piece = game.board.current_piece.clone().unwrap();
+    assert_eq!(current_piece.typ, O);
+    assert_eq!(Some(current_piece.position), game.board.spawn_position(O));
+    assert!(game.hold_used);
+    assert_eq!(game.board.board.get_non_empty_row_count(), 0);
+
+    // 正确放置之后锁定
+    game.on_event(Event::HardDrop).unwrap();
+    assert_eq!(game.pieces, 1);
+    assert_eq!(game.last_finesse_faults, 0);
+    assert_eq!(game.board.current_piece.as_ref().map(|piece| piece.typ), Some(I));
+    assert!(!game.hold_used);
+}
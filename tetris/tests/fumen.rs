@@ -0,0 +1,174 @@
+//! fumen 编码和解码测试
+//!
+//! 测试用的 fumen 字符串按照 tetris-fumen（https://github.com/knewjade/tetris-fumen ）的编码规则独立计算，
+//! 不依赖本库的编码器。期望的小块坐标为 fumen 坐标：x 向右，y 向上，最下面一行为 y = 0。
+
+use tetris::{decode_fumen, encode_fumen, from_center_position, new_default_piece_data, Board, CenterPosition, FumenPage, PieceData};
+
+/// 版面高度
+const HEIGHT: usize = 12;
+
+/// 四个小块的 fumen 坐标
+type Cells = [(isize, isize); 4];
+
+/// 空版面，没有方块和注释
+const EMPTY: &str = "v115@vhAAgH";
+
+/// 单页 fumen：空版面上的一个方块。同一个方块的四个朝向占据相同的格子，
+/// O I S Z 的部分朝向在 fumen 中记录的坐标不是旋转中心，坐标修正的方向错误时这些朝向会错开一格
+const PLACEMENTS: &[(&str, CenterPosition, Cells)] = &[
+    ("v115@vhATLJ", CenterPosition { name: 'O', orientation: 0, x: 4, y: 0 }, [(4, 0), (4, 1), (5, 0), (5, 1)]),
+    ("v115@vhALLJ", CenterPosition { name: 'O', orientation: 1, x: 4, y: 1 }, [(4, 0), (4, 1), (5, 0), (5, 1)]),
+    ("v115@vhADLJ", CenterPosition { name: 'O', orientation: 2, x: 5, y: 1 }, [(4, 0), (4, 1), (5, 0), (5, 1)]),
+    ("v115@vhAbLJ", CenterPosition { name: 'O', orientation: 3, x: 5, y: 0 }, [(4, 0), (4, 1), (5, 0), (5, 1)]),
+    ("v115@vhARQJ", CenterPosition { name: 'I', orientation: 0, x: 4, y: 0 }, [(3, 0), (4, 0), (5, 0), (6, 0)]),
+    ("v115@vhABQJ", CenterPosition { name: 'I', orientation: 2, x: 5, y: 0 }, [(3, 0), (4, 0), (5, 0), (6, 0)]),
+    ("v115@vhApGJ", CenterPosition { name: 'I', orientation: 1, x: 5, y: 2 }, [(5, 0), (5, 1), (5, 2), (5, 3)]),
+    ("v115@vhA5GJ", CenterPosition { name: 'I', orientation: 3, x: 5, y: 1 }, [(5, 0), (5, 1), (5, 2), (5, 3)]),
+    ("v115@vhAXLJ", CenterPosition { name: 'S', orientation: 0, x: 4, y: 0 }, [(3, 0), (4, 0), (4, 1), (5, 1)]),
+    ("v115@vhAHLJ", CenterPosition { name: 'S', orientation: 2, x: 4, y: 1 }, [(3, 0), (4, 0), (4, 1), (5, 1)]),
+    ("v115@vhAvLJ", CenterPosition { name: 'S', orientation: 1, x: 4, y: 1 }, [(4, 1), (4, 2), (5, 0), (5, 1)]),
+    ("v115@vhA/LJ", CenterPosition { name: 'S', orientation: 3, x: 5, y: 1 }, [(4, 1), (4, 2), (5, 0), (5, 1)]),
+    ("v115@vhAULJ", CenterPosition { name: 'Z', orientation: 0, x: 4, y: 0 }, [(3, 1), (4, 0), (4, 1), (5, 0)]),
+    ("v115@vhAELJ", CenterPosition { name: 'Z', orientation: 2, x: 4, y: 1 }, [(3, 1), (4, 0), (4, 1), (5, 0)]),
+    ("v115@vhAMLJ", CenterPosition { name: 'Z', orientation: 1, x: 4, y: 1 }, [(4, 0), (4, 1), (5, 1), (5, 2)]),
+    ("v115@vhAcLJ", CenterPosition { name: 'Z', orientation: 3, x: 5, y: 1 }, [(4, 0), (4, 1), (5, 1), (5, 2)]),
+    ("v115@vhAVQJ", CenterPosition { name: 'T', orientation: 0, x: 4, y: 0 }, [(3, 0), (4, 0), (4, 1), (5, 0)]),
+    ("v115@vhANLJ", CenterPosition { name: 'T', orientation: 1, x: 4, y: 1 }, [(4, 0), (4, 1), (4, 2), (5, 1)]),
+    ("v115@vhAFLJ", CenterPosition { name: 'T', orientation: 2, x: 4, y: 1 }, [(3, 1), (4, 0), (4, 1), (5, 1)]),
+    ("v115@vhAdLJ", CenterPosition { name: 'T', orientation: 3, x: 4, y: 1 }, [(3, 1), (4, 0), (4, 1), (4, 2)]),
+];
+
+/// TSD：T 以 180 度朝向放入槽中，注释为 "TSD"
+const TSD: &str = "v115@HhBtwhglFeC8CeH8AeE8JeFLYDAUNSBA";
+const TSD_BOARD: &str = "
+    ZZIL......
+    GGG...GGGG
+    GGGG.GGGGG
+";
+
+/// 多页 fumen：第一页是上面的 TSD，之后每页在锁定后的版面上放一个方块，最后一页没有方块。
+/// 第三页把注释改为 "I left"（编码时空格转义为 `%20`），之后的页沿用这个注释
+const SEQUENCE: &str = "v115@HhBtwhglFeC8CeH8AeE8JeFLYDAUNSBAvhJzLJ5IYI?AJoo2AsI/dEPIJcBJjEJB2I7zIXiI0kIAgH";
+const SEQUENCE_PIECES: &[(char, Cells)] = &[
+    ('T', [(3, 1), (4, 0), (4, 1), (5, 1)]),
+    ('O', [(5, 0), (5, 1), (6, 0), (6, 1)]),
+    ('I', [(9, 0), (9, 1), (9, 2), (9, 3)]),
+    ('S', [(7, 2), (7, 3), (8, 1), (8, 2)]),
+    ('Z', [(4, 2), (4, 3), (5, 3), (5, 4)]),
+    ('O', [(1, 1), (1, 2), (2, 1), (2, 2)]),
+    ('I', [(1, 5), (2, 5), (3, 5), (4, 5)]),
+    ('O', [(7, 5), (7, 6), (8, 5), (8, 6)]),
+    ('S', [(1, 8), (2, 8), (2, 9), (3, 9)]),
+    ('Z', [(6, 9), (7, 8), (7, 9), (8, 8)]),
+];
+/// 多页 fumen 最后一页的版面：TSD 消除两行之后依次锁定所有方块
+const SEQUENCE_LAST_BOARD: &str = "
+    ..........
+    ..........
+    ..SS..ZZ..
+    .SS....ZZ.
+    ..........
+    .......OO.
+    .IIII..OO.
+    .....Z....
+    ....ZZ.S.I
+    .OO.Z..SSI
+    .OO..OO.SI
+    ZZIL.OO..I
+";
+
+/// 文本版面，与 HEIGHT 高的版面下对齐
+fn board(text: &str) -> Board {
+    let mut board = Board::new(10, HEIGHT);
+    assert!(board.load_text(text));
+    board
+}
+
+/// 当前方块的小块，转为 fumen 坐标并排序
+fn piece_cells(piece_data: &[PieceData], page: &FumenPage) -> (char, Vec<(isize, isize)>) {
+    let piece = page.piece.as_ref().expect("page has no piece");
+    let data = &piece_data[piece.typ];
+    let mut cells: Vec<(isize, isize)> = data.orientation()[piece.orientation]
+        .iter()
+        .map(|(dx, dy)| (piece.position.0 + *dx as isize, HEIGHT as isize - 1 - (piece.position.1 + *dy as isize)))
+        .collect();
+    cells.sort();
+    (data.name.chars().next().unwrap(), cells)
+}
+
+fn page(piece_data: &[PieceData], board: Board, center: Option<&CenterPosition>, comment: &str) -> FumenPage {
+    FumenPage {
+        piece: center.map(|center| from_center_position(piece_data, center, board.height).expect("invalid center position")),
+        board,
+        comment: comment.into(),
+    }
+}
+
+#[test]
+fn empty_field() {
+    let piece_data = new_default_piece_data();
+    let pages = decode_fumen(EMPTY, &piece_data, HEIGHT).unwrap();
+    assert_eq!(pages.len(), 1);
+    assert_eq!(pages[0].board.to_text(), Board::new(10, HEIGHT).to_text());
+    assert!(pages[0].piece.is_none());
+    assert_eq!(pages[0].comment, "");
+    assert_eq!(encode_fumen(&[page(&piece_data, Board::new(10, 22), None, "")], &piece_data), EMPTY);
+}
+
+#[test]
+fn placements_decode_to_cells_and_encode_back() {
+    let piece_data = new_default_piece_data();
+    for (fumen, center, cells) in PLACEMENTS {
+        let pages = decode_fumen(fumen, &piece_data, HEIGHT).unwrap();
+        assert_eq!(pages.len(), 1, "{}", fumen);
+        assert_eq!(piece_cells(&piece_data, &pages[0]), (center.name, cells.to_vec()), "decoding {}", fumen);
+        let encoded = encode_fumen(&[page(&piece_data, Board::new(10, HEIGHT), Some(center), "")], &piece_data);
+        assert_eq!(&encoded, fumen, "encoding {:?}", center);
+    }
+}
+
+#[test]
+fn tsd_page() {
+    let piece_data = new_default_piece_data();
+    let pages = decode_fumen(&format!("https://harddrop.com/fumen/?{}", TSD), &piece_data, HEIGHT).unwrap();
+    assert_eq!(pages.len(), 1);
+    assert_eq!(pages[0].board.to_text(), board(TSD_BOARD).to_text());
+    let (name, cells) = SEQUENCE_PIECES[0];
+    assert_eq!(piece_cells(&piece_data, &pages[0]), (name, cells.to_vec()));
+    assert_eq!(pages[0].comment, "TSD");
+
+    let center = CenterPosition { name: 'T', orientation: 2, x: 4, y: 1 };
+    let tsd = Board::from_text(TSD_BOARD).unwrap();
+    assert_eq!(encode_fumen(&[page(&piece_data, tsd, Some(&center), "TSD")], &piece_data), TSD);
+}
+
+#[test]
+fn multi_page_sequence() {
+    let piece_data = new_default_piece_data();
+    let pages = decode_fumen(SEQUENCE, &piece_data, HEIGHT).unwrap();
+    assert_eq!(pages.len(), SEQUENCE_PIECES.len() + 1);
+    for (i, (page, (name, cells))) in pages.iter().zip(SEQUENCE_PIECES).enumerate() {
+        assert_eq!(piece_cells(&piece_data, page), (*name, cells.to_vec()), "page {}", i + 1);
+    }
+    let comments: Vec<&str> = pages.iter().map(|page| page.comment.as_str()).collect();
+    assert_eq!(comments[..3], ["TSD", "TSD", "I left"]);
+    assert!(comments[3..].iter().all(|comment| *comment == "I left"));
+
+    // 锁定 T 之后消除了两行，第二页只剩最上面一行
+    assert_eq!(pages[0].board.to_text(), board(TSD_BOARD).to_text());
+    assert_eq!(pages[1].board.to_text(), board("ZZIL......").to_text());
+    let last = pages.last().unwrap();
+    assert_eq!(last.board.to_text(), board(SEQUENCE_LAST_BOARD).to_text());
+    assert!(last.piece.is_none());
+
+    // 重新编码后解码得到相同的内容
+    let decoded = decode_fumen(&encode_fumen(&pages, &piece_data), &piece_data, HEIGHT).unwrap();
+    assert_eq!(decoded.len(), pages.len());
+    for (a, b) in pages.iter().zip(&decoded) {
+        assert_eq!(a.board.to_text(), b.board.to_text());
+        let state = |page: &FumenPage| page.piece.as_ref().map(|piece| (piece.typ, piece.position, piece.orientation));
+        assert_eq!(state(a), state(b));
+        assert_eq!(a.comment, b.comment);
+    }
+}
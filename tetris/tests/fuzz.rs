@@ -0,0 +1,184 @@
+//! 随机事件序列模糊测试：随机的设置和版面大小，每个事件之后检查引擎的不变量
+//!
+//! 默认每次测试运行固定数量的种子。设置 `TETRIS_FUZZ_GAMES` 可以跑更多局，
+//! 设置 `TETRIS_FUZZ_SEED` 从指定种子开始（失败信息里会打印种子，方便复现）。
+
+use std::env;
+
+use tetris::{prng, render_game, render_menu, render_title, Config, Event, Framebuffer, Menu, MenuItem, Piece, Replay, ReplayGame, Rgb, Setting, DEFAULT_PIECE_SET, MAX_BOARD_WIDTH};
+
+const EVENTS: [Event; 16] = [
+    Event::Tick,
+    Event::RotateLeft,
+    Event::RotateRight,
+    Event::Rotate180,
+    Event::Hold,
+    Event::HardDrop,
+    Event::SoftDropFast,
+    Event::Forfeit,
+    Event::MoveLeftBegin,
+    Event::MoveLeftEnd,
+    Event::MoveRightBegin,
+    Event::MoveRightEnd,
+    Event::SoftDropBegin,
+    Event::SoftDropEnd,
+    Event::Undo,
+    Event::Redo,
+];
+
+/// 测试用的随机数
+struct Rand(u32);
+
+impl Rand {
+    fn next(&mut self) -> u32 {
+        prng(&mut self.0)
+    }
+
+    /// [low, high] 之间的整数
+    fn range(&mut self, low: usize, high: usize) -> usize {
+        low + self.next() as usize % (high - low + 1)
+    }
+
+    /// [0, high) 之间的小数
+    fn float(&mut self, high: f32) -> f32 {
+        (self.next() % 10000) as f32 / 10000.0 * high
+    }
+
+    fn chance(&mut self, percent: u32) -> bool {
+        self.next() % 100 < percent
+    }
+}
+
+fn env_usize(name: &str, default: usize) -> usize {
+    env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
+
+fn random_config(rand: &mut Rand) -> Config {
+    Config {
+        das: rand.float(20.0),
+        arr: if rand.chance(30) { 0.0 } else { rand.float(5.0) },
+        sdf: if rand.chance(30) { 10000.0 } else { rand.float(40.0) },
+        gravity: if rand.chance(20) { 0.0 } else if rand.chance(10) { 30.0 } else { rand.float(1.0) },
+        lock_delay: rand.range(0, 60),
+        max_reset_times: rand.range(0, 20),
+        finesse_das: rand.chance(50),
+        finesse_training: rand.chance(20),
+        practice: rand.chance(30),
+    }
+}
+
+fn random_replay(rand: &mut Rand) -> Replay {
+    let config = random_config(rand);
+    let width = rand.range(1, MAX_BOARD_WIDTH);
+    let height = rand.range(1, 40);
+    let start_y = rand.range(0, 4) as isize - 2;
+    let preview_count = rand.range(0, 6);
+    Replay::new(rand.next(), DEFAULT_PIECE_SET, width, height, start_y, preview_count, config)
+}
+
+/// 大部分是 Tick，偶尔放弃
+fn random_event(rand: &mut Rand) -> Event {
+    if rand.chance(40) {
+        return Event::Tick;
+    }
+    loop {
+        let event = EVENTS[rand.next() as usize % EVENTS.len()];
+        if event != Event::Forfeit || rand.chance(1) {
+            return event;
+        }
+    }
+}
+
+/// 与 UEFI 版的缓冲区一样直接相加坐标，收到回绕的负坐标时会溢出
+struct CheckedBuffer {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl CheckedBuffer {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![Rgb::default(); width * height],
+        }
+    }
+}
+
+impl Framebuffer for CheckedBuffer {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn fill_rect(&mut self, x: usize, y: usize, cx: usize, cy: usize, color: Rgb) {
+        for y1 in y..(y + cy).min(self.height) {
+            for x1 in x..(x + cx).min(self.width) {
+                self.pixels[y1 * self.width + x1] = color;
+            }
+        }
+    }
+}
+
+/// 每个事件之后都应该成立的条件
+fn check_invariants(game: &ReplayGame, context: &str) {
+    let board = &game.board.board;
+    if let Some(Piece { typ, position, orientation }) = &game.board.current_piece {
+        let piece_data = &game.board.piece_data[*typ];
+        assert!(board.test_piece(piece_data, *position, *orientation), "{}: current piece overlaps locked cells or leaves the board", context);
+    }
+    for y in 0..board.height {
+        assert!(!board.is_row_full(y), "{}: row {} is full after lock", context, y);
+    }
+    assert!(game.reset_times <= game.config.max_reset_times, "{}: reset_times {} > max_reset_times {}", context, game.reset_times, game.config.max_reset_times);
+    if let Some(hold) = game.hold {
+        assert!(hold < game.board.piece_data.len(), "{}: invalid hold {}", context, hold);
+    }
+}
+
+/// 随机大小的缓冲区，包括比版面还小的
+fn check_render(game: &mut ReplayGame, rand: &mut Rand) {
+    let mut buffer = CheckedBuffer::new(rand.range(0, 800), rand.range(0, 600));
+    let hint = game.board.current_piece.clone();
+    render_game(game, &mut buffer, hint.as_ref(), Some(rand.next()));
+    let items = Setting::ALL.iter().map(|setting| MenuItem::with_value(setting.name(), setting.value(&game.config))).collect();
+    let menu = Menu::new("SETTINGS", items);
+    render_title(&mut buffer, rand.float(1.5) - 0.25, &menu);
+    render_menu(&mut buffer, &menu);
+}
+
+/// 一局：随机事件直到游戏结束或者达到事件数上限，然后确认录像可以复现
+fn fuzz_game(seed: u32) {
+    let mut rand = Rand(seed);
+    let mut replay = random_replay(&mut rand);
+    let mut game = replay.new_game(true).unwrap();
+    let max_events = rand.range(1, 3000);
+    for i in 0..max_events {
+        let event = random_event(&mut rand);
+        let result = game.on_event(event);
+        let context = format!("seed {} event {} {:?} ({}x{}, {:?})", seed, i, event, replay.width, replay.height, replay.config);
+        check_invariants(&game, &context);
+        if i % 50 == 0 {
+            check_render(&mut game, &mut rand);
+        }
+        if result.is_err() {
+            break;
+        }
+    }
+    check_render(&mut game, &mut rand);
+    replay.finish(&game);
+    assert!(replay.verify(), "seed {}: replay does not reproduce the game", seed);
+}
+
+#[test]
+fn random_events_keep_invariants() {
+    let start = env_usize("TETRIS_FUZZ_SEED", 1);
+    let games = env_usize("TETRIS_FUZZ_GAMES", 200);
+    for seed in start..start + games {
+        fuzz_game(seed as u32);
+    }
+}
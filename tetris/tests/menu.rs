@@ -0,0 +1,72 @@
+use tetris::{Config, Control, KeyBindings, KeyCode, Menu, MenuInput, MenuItem, Setting};
+
+#[test]
+fn binding_a_used_key_swaps_the_two_controls() {
+    let mut bindings = KeyBindings::default();
+    assert_eq!(bindings.control(KeyCode::Char(' ')), Some(Control::HardDrop));
+    assert_eq!(bindings.key(Control::Hold), KeyCode::Char('z'));
+
+    // 把 Hold 改成空格，硬降换成 Hold 原来的 Z
+    assert!(bindings.bind(Control::Hold, KeyCode::from_char(' ')));
+    assert_eq!(bindings.key(Control::Hold), KeyCode::Char(' '));
+    assert_eq!(bindings.key(Control::HardDrop), KeyCode::Char('z'));
+
+    // 大写字母与小写相同
+    assert!(bindings.bind(Control::Hint, KeyCode::from_char('A')));
+    assert_eq!(bindings.control(KeyCode::Char('a')), Some(Control::Hint));
+    assert_eq!(bindings.control(KeyCode::Char('h')), None);
+
+    // Esc 用于取消，不能绑定
+    assert!(!bindings.bind(Control::Hold, KeyCode::Escape));
+    assert_eq!(bindings.key(Control::Hold), KeyCode::Char(' '));
+
+    // 每个按键最多对应一个操作
+    let keys: Vec<KeyCode> = Control::ALL.iter().map(|control| bindings.key(*control)).collect();
+    assert!(keys.iter().enumerate().all(|(i, key)| !keys[..i].contains(key)));
+}
+
+#[test]
+fn menu_selection_skips_disabled_items_and_wraps() {
+    let items = vec![
+        MenuItem::new("PLAY"),
+        MenuItem::new("RESUME").disabled(),
+        MenuItem::new("SETTINGS"),
+        MenuItem::new("EXIT"),
+    ];
+    let mut menu = Menu::new("TETRIS", items);
+    assert_eq!(menu.selected, 0);
+    assert_eq!(menu.navigate(MenuInput::Down), None);
+    assert_eq!(menu.selected, 2);
+    menu.navigate(MenuInput::Down);
+    menu.navigate(MenuInput::Down);
+    assert_eq!(menu.selected, 0);
+    menu.navigate(MenuInput::Up);
+    assert_eq!(menu.selected, 3);
+    assert_eq!(menu.navigate(MenuInput::Select), Some(MenuInput::Select));
+
+    // 第一项不可用时选中下一个可用的
+    let menu = Menu::new("TETRIS", vec![MenuItem::new("RESUME").disabled(), MenuItem::new("PLAY")]);
+    assert_eq!(menu.selected, 1);
+    assert_eq!(MenuInput::from_key(KeyCode::Enter), Some(MenuInput::Select));
+    assert_eq!(MenuInput::from_key(KeyCode::Char('x')), None);
+}
+
+#[test]
+fn settings_step_and_stop_at_the_limits() {
+    let mut config = Config::default();
+    Setting::Gravity.adjust(&mut config, 1);
+    assert_eq!(config.gravity, 0.05);
+    Setting::Gravity.adjust(&mut config, -1);
+    Setting::Gravity.adjust(&mut config, -1);
+    assert_eq!(Setting::Gravity.value(&config), "0.01");
+    for _ in 0..20 {
+        Setting::Gravity.adjust(&mut config, -1);
+        Setting::LockDelay.adjust(&mut config, -1);
+        Setting::MaxResets.adjust(&mut config, 1);
+    }
+    assert_eq!(config.gravity, 0.0);
+    assert_eq!(config.lock_delay, 0);
+    assert_eq!(config.max_reset_times, 30);
+    Setting::LockDelay.adjust(&mut config, 1);
+    assert_eq!(Setting::LockDelay.value(&config), "5");
+}
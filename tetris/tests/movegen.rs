@@ -0,0 +1,251 @@
+//! 落点搜索和最短路径测试
+//!
+//! 版面使用 `Board::from_text` 的格式，期望的落点用小写字母画出。
+
+use tetris::{find_path, find_path_with_inputs, inputs_to_events, new_default_piece_data, reachable_placements, simulate_path, Board, Config, ExtendedBoard, Game, Input, PieceData, Placement};
+
+type Cells = Vec<(isize, isize)>;
+
+/// 解析版面，小写字母为期望的落点
+///
+/// returns: (Board, usize, Cells) 版面、方块类型、落点占据的格子
+fn parse(text: &str, piece_data: &[PieceData]) -> (Board, usize, Cells) {
+    let lines: Vec<&str> = text.lines().map(|line| line.trim()).filter(|line| !line.is_empty()).collect();
+    let mut cells = Vec::new();
+    let mut typ = None;
+    for (y, line) in lines.iter().enumerate() {
+        for (x, c) in line.chars().enumerate() {
+            if c.is_ascii_lowercase() {
+                typ = piece_data.iter().position(|data| data.name == c.to_ascii_uppercase().to_string());
+                cells.push((x as isize, y as isize));
+            }
+        }
+    }
+    let board = Board::from_text(&lines.join("\n").replace(|c: char| c.is_ascii_lowercase(), ".")).unwrap();
+    cells.sort();
+    (board, typ.expect("no target piece"), cells)
+}
+
+fn cells(piece_data: &PieceData, (x, y): (isize, isize), orientation: usize) -> Cells {
+    let mut cells: Cells = piece_data.orientation()[orientation].iter().map(|(dx, dy)| (x + *dx as isize, y + *dy as isize)).collect();
+    cells.sort();
+    cells
+}
+
+/// 进场位置和朝向
+fn spawn(board: &Board, typ: usize) -> ((isize, isize), usize) {
+    let extended = ExtendedBoard::new(board.width, board.height, new_default_piece_data(), 0);
+    (extended.spawn_position(typ).unwrap(), extended.piece_data[typ].spawn_orientation)
+}
+
+/// 只在进场高度平移和旋转，然后硬降，能到达的所有落点
+fn hard_drop_cells(board: &Board, piece_data: &PieceData) -> Vec<Cells> {
+    let mut result = Vec::new();
+    for orientation in 0..piece_data.orientation().len() {
+        for x in -3..board.width as isize {
+            if board.test_piece(piece_data, (x, 0), orientation) {
+                let dy = board.test_drop_distance(piece_data, (x, 0), orientation);
+                result.push(cells(piece_data, (x, dy), orientation));
+            }
+        }
+    }
+    result
+}
+
+/// 搜索落点，并检查每个落点都已经着地，且没有占据相同格子的重复落点
+fn search(board: &Board, piece_data: &[PieceData], typ: usize) -> Vec<(Cells, Placement)> {
+    let (position, orientation) = spawn(board, typ);
+    let data = &piece_data[typ];
+    let placements = reachable_placements(board, data, position, orientation, true);
+    let mut result: Vec<(Cells, Placement)> = Vec::new();
+    for placement in placements {
+        assert!(!board.test_piece(data, (placement.position.0, placement.position.1 + 1), placement.orientation), "{:?} is not on the ground", placement);
+        let cells = cells(data, placement.position, placement.orientation);
+        assert!(result.iter().all(|(found, _)| *found != cells), "{:?} found twice", cells);
+        result.push((cells, placement));
+    }
+    result
+}
+
+#[test]
+fn tuck_under_an_overhang() {
+    let piece_data = new_default_piece_data();
+    let (board, typ, target) = parse("
+        ..........
+        ..........
+        ..........
+        ..........
+        GGGGGG....
+        ....oo....
+        ....oo....
+    ", &piece_data);
+    let placements = search(&board, &piece_data, typ);
+    assert!(placements.iter().any(|(cells, _)| *cells == target), "tuck not found");
+    // 最左边也能滑进去
+    assert!(placements.iter().any(|(cells, _)| cells.contains(&(0, 5))));
+    assert!(!hard_drop_cells(&board, &piece_data[typ]).contains(&target));
+}
+
+#[test]
+fn t_spin_double_slot() {
+    let piece_data = new_default_piece_data();
+    let (board, typ, target) = parse("
+        ..........
+        ..........
+        ..........
+        GG........
+        GtttGGGGGG
+        GGtGGGGGGG
+    ", &piece_data);
+    let placements = search(&board, &piece_data, typ);
+    let (_, placement) = placements.iter().find(|(cells, _)| *cells == target).expect("T-spin slot not found");
+    assert_eq!(placement.orientation, 2);
+    assert!(placement.spin);
+    assert!(!hard_drop_cells(&board, &piece_data[typ]).contains(&target));
+}
+
+#[test]
+fn every_hard_drop_is_reachable_on_a_flat_board() {
+    let piece_data = new_default_piece_data();
+    let board = Board::new(10, 22);
+    for typ in 0..piece_data.len() {
+        let placements: Vec<Cells> = search(&board, &piece_data, typ).into_iter().map(|(cells, _)| cells).collect();
+        let mut hard_drops = hard_drop_cells(&board, &piece_data[typ]);
+        hard_drops.sort();
+        hard_drops.dedup();
+        assert_eq!(placements.len(), hard_drops.len(), "piece {}", piece_data[typ].name);
+        assert!(hard_drops.iter().all(|cells| placements.contains(cells)), "piece {}", piece_data[typ].name);
+    }
+}
+
+/// 从进场状态到小写字母画出的落点的最短操作序列
+fn path(text: &str, moves: &[Input]) -> Vec<Input> {
+    let piece_data = new_default_piece_data();
+    let (board, typ, target) = parse(text, &piece_data);
+    let (_, placement) = search(&board, &piece_data, typ).into_iter().find(|(cells, _)| *cells == target).expect("target not reachable");
+    find_path_with_inputs(&board, &piece_data[typ], spawn(&board, typ), (placement.position, placement.orientation), moves).expect("no path")
+}
+
+#[test]
+fn find_path_uses_the_fewest_inputs() {
+    let moves = Input::moves(true, true);
+    assert_eq!(path("
+        ..........
+        ..........
+        ..........
+        ....t.....
+        ...ttt....
+    ", &moves), [Input::HardDrop]);
+    assert_eq!(path("
+        ..........
+        ..........
+        ..........
+        ...oo.....
+        ...oo.....
+    ", &moves), [Input::Left, Input::HardDrop]);
+    assert_eq!(path("
+        ..........
+        ..........
+        ..........
+        oo........
+        oo........
+    ", &moves), [Input::DasLeft, Input::HardDrop]);
+
+    // 竖着的 I 放在最左边：旋转和 DAS 各一次
+    let i_left = path("
+        ..........
+        i.........
+        i.........
+        i.........
+        i.........
+    ", &moves);
+    assert_eq!(i_left.len(), 3, "{:?}", i_left);
+    assert!(i_left.contains(&Input::DasLeft));
+    assert_eq!(i_left.last(), Some(&Input::HardDrop));
+
+    // T-spin 必须以旋转结束
+    let tsd = path("
+        ..........
+        ..........
+        ..........
+        GG........
+        GtttGGGGGG
+        GGtGGGGGGG
+    ", &moves);
+    assert!(matches!(tsd[tsd.len() - 2], Input::RotateLeft | Input::RotateRight | Input::Rotate180), "{:?}", tsd);
+}
+
+#[test]
+fn find_path_without_das_moves_one_column_at_a_time() {
+    let moves = Input::moves(true, false);
+    assert_eq!(path("
+        ..........
+        ..........
+        ..........
+        oo........
+        oo........
+    ", &moves), [Input::Left, Input::Left, Input::Left, Input::Left, Input::HardDrop]);
+}
+
+/// O 藏进左边的屋檐下：软降之后 DAS 到墙
+const TUCK: &str = "
+    ..........
+    ..........
+    ..........
+    ..........
+    GGGGGG....
+    oo........
+    oo........
+";
+
+type TuckGame = Game<std::vec::IntoIter<usize>>;
+
+/// 有重力、锁定延迟很短的游戏，当前方块为 TUCK 中的 O
+fn tuck_game() -> (TuckGame, ((isize, isize), usize)) {
+    let config = Config {
+        das: 7.0,
+        arr: 2.0,
+        gravity: 0.5,
+        lock_delay: 15,
+        ..Config::default()
+    };
+    let piece_data = new_default_piece_data();
+    let (board, typ, target) = parse(TUCK, &piece_data);
+    let (_, placement) = search(&board, &piece_data, typ).into_iter().find(|(cells, _)| *cells == target).expect("target not reachable");
+    let mut extended = ExtendedBoard::new(board.width, board.height, piece_data, 0);
+    extended.board = board;
+    let mut game = Game::new(config, extended, vec![typ, 0, 0].into_iter());
+    game.add_next_piece().unwrap();
+    (game, (placement.position, placement.orientation))
+}
+
+#[test]
+fn simulated_path_survives_gravity_and_lock_delay() {
+    let piece_data = new_default_piece_data();
+    let (_, typ, target_cells) = parse(TUCK, &piece_data);
+
+    let in_tuck = |game: &TuckGame| target_cells.iter().all(|(x, y)| game.board.board.get_cell(*x as usize, *y as usize) == Some(&Some(piece_data[typ].color_id)));
+
+    // 不考虑重力的事件序列：DAS 的 Tick 太多，方块在途中下落、锁定
+    let (mut game, target) = tuck_game();
+    let current_piece = game.board.current_piece.clone().unwrap();
+    let inputs = find_path(&game.board.board, &piece_data[typ], (current_piece.position, current_piece.orientation), target, true).unwrap();
+    assert!(inputs.contains(&Input::DasLeft), "{:?}", inputs);
+    for event in inputs_to_events(&inputs, &game.config, game.board.board.width) {
+        if game.on_event(event).is_err() {
+            break;
+        }
+    }
+    assert!(!in_tuck(&game));
+
+    // 模拟得到的事件序列准确放到落点
+    let (mut game, target) = tuck_game();
+    let mut simulation = game.with_rng(std::iter::empty());
+    let events = simulate_path(&mut simulation, target, &Input::moves(true, true)).unwrap();
+    assert!(events.contains(&tetris::Event::Tick));
+    for event in events {
+        game.on_event(event).unwrap();
+    }
+    assert_eq!(game.pieces, 1);
+    assert!(in_tuck(&game));
+}
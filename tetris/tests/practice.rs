@@ -0,0 +1,79 @@
+//! 练习模式的撤销和重做测试
+
+use tetris::{new_default_piece_data, Config, Event, ExtendedBoard, Game, PreviewGenerator, ReplayGame, SevenBagGenerator};
+
+fn new_game() -> ReplayGame {
+    let config = Config {
+        gravity: 0.0,
+        practice: true,
+        ..Config::default()
+    };
+    let board = ExtendedBoard::new(10, 22, new_default_piece_data(), 0);
+    let mut game = Game::new(config, board, PreviewGenerator::new(SevenBagGenerator::new(1, 7), 5));
+    game.enable_undo();
+    game.add_next_piece().unwrap();
+    game
+}
+
+/// 撤销和重做会恢复的状态：版面、当前方块、Hold、方块数和预览
+type State = (String, Option<usize>, Option<usize>, usize, Vec<usize>);
+
+fn state(game: &mut ReplayGame) -> State {
+    (
+        game.board.board.to_text(),
+        game.board.current_piece.as_ref().map(|piece| piece.typ),
+        game.hold,
+        game.pieces,
+        game.rng.preview().copied().collect(),
+    )
+}
+
+#[test]
+fn undo_and_redo_locked_pieces() {
+    let mut game = new_game();
+    let start = state(&mut game);
+    game.on_event(Event::Hold).unwrap();
+    game.on_event(Event::HardDrop).unwrap();
+    let first = state(&mut game);
+    game.on_event(Event::MoveLeftBegin).unwrap();
+    game.on_event(Event::MoveLeftEnd).unwrap();
+    game.on_event(Event::HardDrop).unwrap();
+    let second = state(&mut game);
+    assert_eq!((first.3, second.3), (1, 2));
+    assert_ne!(first.0, second.0);
+
+    // 撤销回到方块进场时，Hold 之前
+    game.on_event(Event::MoveRightBegin).unwrap();
+    game.on_event(Event::Undo).unwrap();
+    assert_eq!(state(&mut game), first);
+    assert_eq!((game.move_direction, game.last_finesse_faults), (0, 0));
+    game.on_event(Event::Undo).unwrap();
+    assert_eq!(state(&mut game), start);
+    assert!(!game.hold_used);
+    game.on_event(Event::Undo).unwrap();
+    assert_eq!(state(&mut game), start);
+
+    game.on_event(Event::Redo).unwrap();
+    assert_eq!(state(&mut game), first);
+    assert_eq!(game.redo_stack.len(), 1);
+
+    // 锁定新的方块后不能再重做
+    game.on_event(Event::HardDrop).unwrap();
+    assert!(game.redo_stack.is_empty());
+    let third = state(&mut game);
+    assert_ne!(third, second);
+    game.on_event(Event::Redo).unwrap();
+    assert_eq!(state(&mut game), third);
+    game.on_event(Event::Undo).unwrap();
+    assert_eq!(state(&mut game), first);
+}
+
+#[test]
+fn undo_is_ignored_outside_practice() {
+    let mut game = new_game();
+    game.config.practice = false;
+    game.on_event(Event::HardDrop).unwrap();
+    let locked = state(&mut game);
+    game.on_event(Event::Undo).unwrap();
+    assert_eq!(state(&mut game), locked);
+}
@@ -0,0 +1,137 @@
+//! 存档格式测试
+
+use tetris::{decode_save, encode_save, new_default_piece_data, Board, Config, Event, ExtendedBoard, Game, PieceData, PieceTypes, PreviewGenerator, Replay, ReplayGame, SevenBagGenerator, Writer, COLOR_TABLE, MAX_BOARD_HEIGHT, MAX_BOARD_WIDTH, MAX_TYPE_COUNT, SAVE_MAGIC, SAVE_VERSION};
+
+/// 当前版本的存档头
+fn save_writer() -> Writer {
+    let mut writer = Writer::new();
+    writer.write_bytes(SAVE_MAGIC);
+    writer.write_u8(SAVE_VERSION);
+    writer
+}
+
+/// 只包含一个版面的存档：版面大小之后是指定个数的空格子
+fn board_save(width: usize, height: usize, cells: usize) -> Vec<u8> {
+    board_save_with(width, height, &vec![0; cells])
+}
+
+/// 只包含一个版面的存档，格子为 0（空）或者颜色编号加 1
+fn board_save_with(width: usize, height: usize, cells: &[usize]) -> Vec<u8> {
+    let mut writer = save_writer();
+    writer.write_usize(width);
+    writer.write_usize(height);
+    for cell in cells {
+        writer.write_usize(*cell);
+    }
+    writer.into_bytes()
+}
+
+#[test]
+fn board_round_trip() {
+    let board = Board::from_text("
+        ..G
+        IT.
+    ").unwrap();
+    let decoded: Board = decode_save(&encode_save(&board)).unwrap();
+    assert_eq!(decoded.to_text(), board.to_text());
+}
+
+#[test]
+fn rejects_board_with_invalid_size() {
+    assert!(decode_save::<Board>(&board_save(3, 2, 6)).is_some());
+    // 宽度为 0 时 width * height 为 0，长度检查拦不住
+    assert!(decode_save::<Board>(&board_save(0, usize::MAX, 0)).is_none());
+    assert!(decode_save::<Board>(&board_save(0, 5, 0)).is_none());
+    assert!(decode_save::<Board>(&board_save(3, 0, 0)).is_none());
+    assert!(decode_save::<Board>(&board_save(MAX_BOARD_WIDTH + 1, 1, MAX_BOARD_WIDTH + 1)).is_none());
+    assert!(decode_save::<Board>(&board_save(1, MAX_BOARD_HEIGHT + 1, MAX_BOARD_HEIGHT + 1)).is_none());
+}
+
+#[test]
+fn rejects_colors_outside_the_color_table() {
+    let last = COLOR_TABLE.len();
+    assert!(decode_save::<Board>(&board_save_with(2, 1, &[last, 0])).is_some());
+    assert!(decode_save::<Board>(&board_save_with(2, 1, &[last + 1, 0])).is_none());
+    assert!(decode_save::<Board>(&board_save_with(2, 1, &[0, usize::MAX])).is_none());
+
+    let mut piece_data = new_default_piece_data().swap_remove(0);
+    piece_data.color_id = last - 1;
+    assert!(decode_save::<PieceData>(&encode_save(&piece_data)).is_some());
+    piece_data.color_id = last;
+    assert!(decode_save::<PieceData>(&encode_save(&piece_data)).is_none());
+}
+
+type Generator = PreviewGenerator<usize, SevenBagGenerator>;
+
+#[test]
+fn rejects_generators_out_of_range() {
+    for type_count in [0, MAX_TYPE_COUNT + 1] {
+        assert!(decode_save::<SevenBagGenerator>(&encode_save(&SevenBagGenerator::new(1, type_count))).is_none());
+    }
+
+    // 预览比预览个数多
+    let preview_save = |preview_count: usize, preview: &[usize]| {
+        let mut writer = save_writer();
+        writer.write(&SevenBagGenerator::new(1, 7));
+        writer.write_usize(preview_count);
+        writer.write(preview);
+        writer.into_bytes()
+    };
+    assert!(decode_save::<Generator>(&preview_save(2, &[0, 6])).unwrap().has_type_count(7));
+    assert!(decode_save::<Generator>(&preview_save(1, &[0, 6])).is_none());
+    // 预览中的方块超出方块组，由 Game 拒绝
+    assert!(!decode_save::<Generator>(&preview_save(2, &[0, 7])).unwrap().has_type_count(7));
+}
+
+#[test]
+fn rejects_game_with_mismatched_generator() {
+    let game_save = |type_count: usize| {
+        let board = ExtendedBoard::new(10, 22, new_default_piece_data(), 0);
+        let mut game = Game::new(Config::default(), board, PreviewGenerator::new(SevenBagGenerator::new(1, type_count), 5));
+        game.add_next_piece().unwrap();
+        encode_save(&game)
+    };
+    assert!(decode_save::<ReplayGame>(&game_save(7)).is_some());
+    assert!(decode_save::<ReplayGame>(&game_save(6)).is_none());
+}
+
+/// 版本 1 的存档（练习模式加入之前）：(ReplayGame, Replay)，种子 7，das 8，打开 finesse 训练。
+/// 左移并硬降 L，Hold O，旋转当前方块，共 3 个 Tick
+const SAVE_V1: &[u8] = include_bytes!("data/save_v1.sav");
+
+#[test]
+fn reads_version_1_save() {
+    let (mut game, replay): (ReplayGame, Replay) = decode_save(SAVE_V1).unwrap();
+    assert!(!game.config.practice);
+    assert!(game.config.finesse_training);
+    assert_eq!(game.config.das, 8.0);
+    assert_eq!(replay.config, game.config);
+    assert_eq!((replay.seed, replay.width, replay.height, replay.preview_count), (7, 10, 22, 5));
+    assert_eq!((game.pieces, game.hold, game.current_tick), (1, Some(3), 3));
+    assert_eq!(game.checksum(), 0xd85b144cab5b1da1);
+    let mut board = Board::new(10, 22);
+    assert!(board.load_text("
+        ....L.....
+        ..LLL.....
+    "));
+    assert_eq!(game.board.board.to_text(), board.to_text());
+    // 旧版本没有 t_spin，按名称判定
+    assert!(game.board.piece_data.iter().all(|data| data.t_spin == (data.name == "T")));
+
+    // 继续游戏，再保存为当前版本
+    game.on_event(Event::HardDrop).unwrap();
+    let data = encode_save(&(&game, &replay));
+    assert_eq!(data[SAVE_MAGIC.len()], SAVE_VERSION);
+    let (resumed, _): (ReplayGame, Replay) = decode_save(&data).unwrap();
+    assert_eq!(resumed.checksum(), game.checksum());
+    assert!(resumed.board.piece_data.iter().all(|data| data.t_spin == (data.name == "T")));
+}
+
+#[test]
+fn rejects_unknown_version() {
+    let mut data = encode_save(&Board::new(4, 4));
+    data[SAVE_MAGIC.len()] = SAVE_VERSION + 1;
+    assert!(decode_save::<Board>(&data).is_none());
+    data[SAVE_MAGIC.len()] = 0;
+    assert!(decode_save::<Board>(&data).is_none());
+}
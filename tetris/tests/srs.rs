@@ -0,0 +1,387 @@
+//! SRS 旋转和踢墙的场景测试
+//!
+//! 每个场景是一个文本版面加一串操作。版面用 `tetris::Board::from_text` 的格式，当前方块用小写字母画出，
+//! 操作依次交给 `Board::test_move_piece`，最后的位置和方向画出来应该与期望的版面完全一致。
+//! 踢墙表或者坐标约定（例如踢墙表里 y 向上为正）有任何变化都会让某个场景失败。
+//!
+//! 操作字符：`<` `>` 左右平移，`v` 下降一格，`x` 顺时针，`z` 逆时针，`a` 180 度。
+//! 无法移动时方块留在原地，与游戏中一样。
+
+use tetris::{new_default_piece_data, Board, PieceData};
+
+struct Scenario {
+    name: &'static str,
+    /// 当前方块的初始方向，0: 进场, 1: R, 2: 180 度, 3: L。I、S、Z 的一些方向画出来形状相同，只看版面无法区分
+    orientation: usize,
+    inputs: &'static str,
+    before: &'static str,
+    after: &'static str,
+}
+
+const SCENARIOS: &[Scenario] = &[
+    // TST 以及 Fin、Neo、Imperial 等三消 T-spin 都要靠向下两格的踢墙，下面几个场景分别覆盖
+    Scenario {
+        name: "TST: T slides under the overhang, 0->R kicks (-1, -2)",
+        orientation: 0,
+        inputs: "<x",
+        before: "
+            ..........
+            GGGG.t....
+            GGG.ttt...
+            GGG.GGGGGG
+            GGG..GGGGG
+            GGG.GGGGGG
+        ",
+        after: "
+            ..........
+            GGGG......
+            GGG.......
+            GGGtGGGGGG
+            GGGttGGGGG
+            GGGtGGGGGG
+        ",
+    },
+    Scenario {
+        name: "TST mirrored: 0->L kicks (1, -2)",
+        orientation: 0,
+        inputs: "z",
+        before: "
+            ..........
+            .....tGGGG
+            ....tttGGG
+            GGGGGG.GGG
+            GGGGG..GGG
+            GGGGGG.GGG
+        ",
+        after: "
+            ..........
+            ......GGGG
+            .......GGG
+            GGGGGGtGGG
+            GGGGGttGGG
+            GGGGGGtGGG
+        ",
+    },
+    Scenario {
+        name: "upside-down T drops straight into a triple slot, 2->R kicks (0, -2)",
+        orientation: 2,
+        inputs: "z",
+        before: "
+            ..........
+            GGGGG.....
+            GGGttt....
+            GGGGtGGGGG
+            GGGG..GGGG
+            GGGG.GGGGG
+        ",
+        after: "
+            ..........
+            GGGGG.....
+            GGG.......
+            GGGGtGGGGG
+            GGGGttGGGG
+            GGGGtGGGGG
+        ",
+    },
+    Scenario {
+        name: "upside-down T steps sideways into a triple slot, 2->L kicks (1, -2)",
+        orientation: 2,
+        inputs: "x",
+        before: "
+            ..........
+            GGGGGG....
+            GGGttt....
+            GGGGt.GGGG
+            GGGG..GGGG
+            GGGGG.GGGG
+        ",
+        after: "
+            ..........
+            GGGGGG....
+            GGG.......
+            GGGG.tGGGG
+            GGGGttGGGG
+            GGGGGtGGGG
+        ",
+    },
+    Scenario {
+        name: "T boxed in: every kick fails and the piece stays",
+        orientation: 0,
+        inputs: "xza",
+        before: "
+            GGGGGGGGGG
+            GGGGtGGGGG
+            GGGtttGGGG
+            GGGGGGGGGG
+        ",
+        after: "
+            GGGGGGGGGG
+            GGGGtGGGGG
+            GGGtttGGGG
+            GGGGGGGGGG
+        ",
+    },
+    // I 方块使用单独的踢墙表
+    Scenario {
+        name: "I against the left wall, L->0 kicks (1, 0)",
+        orientation: 3,
+        inputs: "x",
+        before: "
+            ..........
+            i.........
+            i.........
+            i.........
+            i.........
+            ..........
+        ",
+        after: "
+            ..........
+            ..........
+            iiii......
+            ..........
+            ..........
+            ..........
+        ",
+    },
+    Scenario {
+        name: "I against the right wall, R->0 kicks (-1, 0)",
+        orientation: 1,
+        inputs: "z",
+        before: "
+            ..........
+            .........i
+            .........i
+            .........i
+            .........i
+            ..........
+        ",
+        after: "
+            ..........
+            ..........
+            ......iiii
+            ..........
+            ..........
+            ..........
+        ",
+    },
+    Scenario {
+        name: "I on the floor, 0->R kicks (1, 2) upwards",
+        orientation: 0,
+        inputs: "x",
+        before: "
+            ..........
+            ..........
+            ..........
+            ..........
+            ..........
+            ...iiii...
+        ",
+        after: "
+            ..........
+            ..........
+            ......i...
+            ......i...
+            ......i...
+            ......i...
+        ",
+    },
+    // 180 度旋转的踢墙表
+    Scenario {
+        name: "I 180 in the air: R->L shifts one column left without a kick",
+        orientation: 1,
+        inputs: "a",
+        before: "
+            ..........
+            .....i....
+            .....i....
+            .....i....
+            .....i....
+            ..........
+        ",
+        after: "
+            ..........
+            ....i.....
+            ....i.....
+            ....i.....
+            ....i.....
+            ..........
+        ",
+    },
+    Scenario {
+        name: "I 180 in a well: R->L kicks (1, 0) and stays in the well",
+        orientation: 1,
+        inputs: "a",
+        before: "
+            ..........
+            GGGGGiGGGG
+            GGGGGiGGGG
+            GGGGGiGGGG
+            GGGGGiGGGG
+            GGGGGGGGGG
+        ",
+        after: "
+            ..........
+            GGGGGiGGGG
+            GGGGGiGGGG
+            GGGGGiGGGG
+            GGGGGiGGGG
+            GGGGGGGGGG
+        ",
+    },
+    Scenario {
+        name: "T 180 on the floor, 0->2 kicks (0, 1) upwards",
+        orientation: 0,
+        inputs: "a",
+        before: "
+            ..........
+            ..........
+            ....t.....
+            ...ttt....
+        ",
+        after: "
+            ..........
+            ..........
+            ...ttt....
+            ....t.....
+        ",
+    },
+    Scenario {
+        name: "T 180 under an overhang, 2->0 falls through to the last test (1, 0)",
+        orientation: 2,
+        inputs: "a",
+        before: "
+            ..........
+            ..........
+            GGGGG.....
+            GGGttt....
+            GGGGtGGGG.
+            GGGGGGGGG.
+        ",
+        after: "
+            ..........
+            ..........
+            GGGGGt....
+            GGG.ttt...
+            GGGG.GGGG.
+            GGGGGGGGG.
+        ",
+    },
+    // J L S Z 共用踢墙表
+    Scenario {
+        name: "J against the left wall, R->0 kicks (1, 0)",
+        orientation: 1,
+        inputs: "z",
+        before: "
+            ..........
+            jj........
+            j.........
+            j.........
+        ",
+        after: "
+            ..........
+            j.........
+            jjj.......
+            ..........
+        ",
+    },
+    Scenario {
+        name: "Z spin triple, 0->R kicks (0, -2)",
+        orientation: 0,
+        inputs: "x",
+        before: "
+            ..........
+            ...zz.....
+            GGGGzz....
+            GGGGG.GGGG
+            GGGG..GGGG
+            GGGG.GGGGG
+        ",
+        after: "
+            ..........
+            ..........
+            GGGG......
+            GGGGGzGGGG
+            GGGGzzGGGG
+            GGGGzGGGGG
+        ",
+    },
+];
+
+/// 当前方块：类型、位置、方向
+type State = (usize, (isize, isize), usize);
+
+/// 解析场景的版面，小写字母为当前方块，其余交给 Board::from_text
+fn parse(text: &str, orientation: usize, piece_data: &[PieceData]) -> (Board, State) {
+    let lines: Vec<&str> = text.lines().map(|line| line.trim()).filter(|line| !line.is_empty()).collect();
+    let mut cells = Vec::new();
+    let mut typ = None;
+    for (y, line) in lines.iter().enumerate() {
+        for (x, c) in line.chars().enumerate() {
+            if c.is_ascii_lowercase() {
+                let index = piece_data.iter().position(|data| data.name == c.to_ascii_uppercase().to_string()).expect("unknown piece");
+                assert!(typ.is_none() || typ == Some(index), "more than one current piece");
+                typ = Some(index);
+                cells.push((x as isize, y as isize));
+            }
+        }
+    }
+    let typ = typ.expect("no current piece");
+    let board = Board::from_text(&lines.join("\n").replace(|c: char| c.is_ascii_lowercase(), ".")).expect("invalid board");
+    cells.sort();
+    // 按左上角的小块对齐，求出位置
+    let shape = &piece_data[typ].orientation()[orientation];
+    let (min_x, min_y) = shape.iter().map(|(x, y)| (*x as isize, *y as isize)).min().unwrap();
+    let position = (cells[0].0 - min_x, cells[0].1 - min_y);
+    let mut shape: Vec<_> = shape.iter().map(|(x, y)| (position.0 + *x as isize, position.1 + *y as isize)).collect();
+    shape.sort();
+    assert_eq!(shape, cells, "current piece does not match orientation {}", orientation);
+    (board, (typ, position, orientation))
+}
+
+/// 把当前方块用小写字母画到版面上
+fn draw(board: &Board, piece_data: &[PieceData], (typ, position, orientation): State) -> String {
+    let mut lines: Vec<Vec<char>> = board.to_text().lines().map(|line| line.chars().collect()).collect();
+    let letter = piece_data[typ].name.to_ascii_lowercase().chars().next().unwrap();
+    for (x, y) in &piece_data[typ].orientation()[orientation] {
+        lines[(position.1 + *y as isize) as usize][(position.0 + *x as isize) as usize] = letter;
+    }
+    lines.iter().map(|line| line.iter().collect::<String>() + "\n").collect()
+}
+
+fn normalize(text: &str) -> String {
+    text.lines().map(|line| line.trim()).filter(|line| !line.is_empty()).map(|line| line.to_string() + "\n").collect()
+}
+
+fn run(scenario: &Scenario, piece_data: &[PieceData]) -> String {
+    let (board, (typ, mut position, mut orientation)) = parse(scenario.before, scenario.orientation, piece_data);
+    assert!(board.test_piece(&piece_data[typ], position, orientation), "{}: current piece overlaps the board", scenario.name);
+    for input in scenario.inputs.chars() {
+        let (translation, rotation) = match input {
+            '<' => ((-1, 0), 0),
+            '>' => ((1, 0), 0),
+            'v' => ((0, 1), 0),
+            'x' => ((0, 0), 1),
+            'z' => ((0, 0), 3),
+            'a' => ((0, 0), 2),
+            _ => panic!("{}: unknown input {:?}", scenario.name, input),
+        };
+        if let Some((new_position, new_orientation)) = board.test_move_piece(&piece_data[typ], position, orientation, translation, rotation) {
+            position = new_position;
+            orientation = new_orientation;
+        }
+    }
+    draw(&board, piece_data, (typ, position, orientation))
+}
+
+#[test]
+fn srs_scenarios() {
+    let piece_data = new_default_piece_data();
+    let mut failures = Vec::new();
+    for scenario in SCENARIOS {
+        let actual = run(scenario, &piece_data);
+        if actual != normalize(scenario.after) {
+            failures.push(format!("{} ({}):\nexpected\n{}actual\n{}", scenario.name, scenario.inputs, normalize(scenario.after), actual));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}